@@ -4,7 +4,7 @@ use std::path::PathBuf;
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 
-use es_module_lexer::parse;
+use es_module_lexer::{parse, parse_header, Lexer};
 use std::time::Duration;
 
 fn parse_fixtures(c: &mut Criterion) {
@@ -26,14 +26,66 @@ fn parse_fixtures(c: &mut Criterion) {
     let mut group = c.benchmark_group("parse_fixtures");
     group.measurement_time(Duration::from_secs(20));
 
-    for (path, size, content) in fixtures {
-        group.throughput(Throughput::Bytes(size));
+    for (path, size, content) in &fixtures {
+        group.throughput(Throughput::Bytes(*size));
         group.bench_with_input(
             BenchmarkId::from_parameter(path.to_str().unwrap()),
-            &content,
+            content,
             |b, content| b.iter(|| parse(content)),
         );
     }
+    group.finish();
+
+    let mut group = c.benchmark_group("lexer_reuse");
+    group.measurement_time(Duration::from_secs(20));
+    group.throughput(Throughput::Bytes(
+        fixtures.iter().map(|(_, size, _)| size).sum(),
+    ));
+    group.bench_function("all fixtures", |b| {
+        let mut lexer = Lexer::new();
+        b.iter(|| {
+            for (_, _, content) in &fixtures {
+                lexer.parse(content).unwrap();
+            }
+        })
+    });
+    group.bench_function("all fixtures, new buffers", |b| {
+        b.iter(|| {
+            for (_, _, content) in &fixtures {
+                parse(content).unwrap();
+            }
+        })
+    });
+    group.finish();
+
+    #[cfg(feature = "parallel")]
+    {
+        let mut group = c.benchmark_group("parse_many");
+        group.measurement_time(Duration::from_secs(20));
+        group.throughput(Throughput::Bytes(
+            fixtures.iter().map(|(_, size, _)| size).sum(),
+        ));
+        group.bench_function("all fixtures", |b| {
+            b.iter(|| {
+                es_module_lexer::parse_many(
+                    fixtures
+                        .iter()
+                        .map(|(path, _, content)| (path, content.as_str())),
+                )
+            })
+        });
+        group.finish();
+    }
+
+    let mut group = c.benchmark_group("parse_header");
+    for (path, size, content) in &fixtures {
+        group.throughput(Throughput::Bytes(*size));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(path.to_str().unwrap()),
+            content,
+            |b, content| b.iter(|| parse_header(content)),
+        );
+    }
     group.finish()
 }
 
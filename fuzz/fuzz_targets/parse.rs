@@ -0,0 +1,35 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use es_module_lexer::{parse, parse_bytes, parse_with_recovery, pretty_error, Import};
+
+#[path = "../../tests/common/ranges.rs"]
+mod ranges;
+
+use ranges::check_ranges;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(analysis) = parse_bytes(data) {
+        for import in &analysis.imports {
+            if let Import::Static(i) = import {
+                let _ = i.module_specifier(data);
+            }
+        }
+        for export in &analysis.exports {
+            let _ = export.name(data);
+        }
+    }
+    if let Ok(source) = std::str::from_utf8(data) {
+        match parse(source) {
+            Ok(analysis) => check_ranges(source, &analysis),
+            Err(err) => {
+                pretty_error(source, &err);
+            }
+        }
+        let (analysis, errors) = parse_with_recovery(source);
+        check_ranges(source, &analysis);
+        for err in &errors {
+            pretty_error(source, err);
+        }
+    }
+});
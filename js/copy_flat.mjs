@@ -0,0 +1,39 @@
+/*
+ * Copies the flat result decoder next to the wasm-pack output in the given directory,
+ * as CommonJS for the `--target nodejs` output, and adds it to the files of the package.
+ *
+ * Usage: node js/copy_flat.mjs wasm_node
+ */
+import fs from 'fs';
+import path from 'path';
+import { fileURLToPath } from 'url';
+
+const dir = process.argv[2];
+if (!dir) {
+	console.error('usage: node js/copy_flat.mjs <wasm-pack output directory>');
+	process.exit(1);
+}
+
+const jsDir = path.dirname(fileURLToPath(import.meta.url));
+const packageJson = path.join(dir, 'package.json');
+const pkg = JSON.parse(fs.readFileSync(packageJson, 'utf8'));
+
+let flat = fs.readFileSync(path.join(jsDir, 'flat.js'), 'utf8');
+// only `--target nodejs` emits CommonJS glue, the other targets are ES modules
+if (pkg.type !== 'module' && pkg.module === undefined) {
+	const replace = (pattern, replacement) => {
+		if (!pattern.test(flat)) throw new Error(`js/flat.js no longer matches ${pattern}`);
+		flat = flat.replace(pattern, replacement);
+	};
+	const exported = [...flat.matchAll(/^export function (\w+)/gm)].map(match => match[1]);
+	replace(/^import (\{[^}]*\}) from ('[^']*')$/m, 'const $1 = require($2)');
+	replace(/^export function /gm, 'function ');
+	flat += `\nmodule.exports = { ${exported.join(', ')} }\n`;
+}
+fs.writeFileSync(path.join(dir, 'flat.js'), flat);
+fs.copyFileSync(path.join(jsDir, 'flat.d.ts'), path.join(dir, 'flat.d.ts'));
+
+if (pkg.files) {
+	pkg.files = [...new Set([...pkg.files, 'flat.js', 'flat.d.ts'])];
+	fs.writeFileSync(packageJson, JSON.stringify(pkg, null, 2) + '\n');
+}
@@ -0,0 +1,16 @@
+import { CompatResult, ParseOptions } from './es_module_lexer.js'
+
+/**
+ * Parses the source like `parseCompat`, returning `[imports, exports, facade, hasModuleSyntax]`.
+ *
+ * Unlike `parseCompat`, escape sequences in the names `n` are not decoded.
+ *
+ * @throws {CompatParseError} Like `parseCompat`.
+ */
+export function parseFlat(source: string, name?: string, options?: ParseOptions): CompatResult;
+
+/**
+ * Decodes a result buffer returned by `parseSourceBuffer`,
+ * which must be decoded before calling into the wasm module again.
+ */
+export function decode(source: string, buffer: Uint32Array): CompatResult;
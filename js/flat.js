@@ -0,0 +1,64 @@
+/*
+ * Decoder for the flat result buffer of `parseSourceBuffer`, see `src/wasm_flat.rs` for its layout.
+ * Copied next to the wasm-pack output by `js/copy_flat.mjs`, as CommonJS for the Node package.
+ */
+import { sourceBuffer, parseSourceBuffer } from './es_module_lexer.js'
+
+const encoder = new TextEncoder()
+// the local offsets of an export without a local name
+const NO_LOCAL_NAME = 0xffffffff
+
+/**
+ * Parses the source like `parseCompat`, returning `[imports, exports, facade, hasModuleSyntax]`.
+ *
+ * The source is encoded straight into wasm memory and the results are read from it without
+ * creating objects on the Rust side. Unlike `parseCompat`, escape sequences in the names `n`
+ * are not decoded.
+ */
+export function parseFlat(source, name, options) {
+    // UTF-8 takes at most 3 bytes per UTF-16 code unit
+    const buffer = sourceBuffer(source.length * 3)
+    const { written } = encoder.encodeInto(source, buffer)
+    return decode(source, parseSourceBuffer(written, name, options))
+}
+
+/**
+ * Decodes a result buffer. It must be decoded before calling into the wasm module again.
+ */
+export function decode(source, buffer) {
+    const [importCount, exportCount, facade, hasModuleSyntax] = buffer
+    const imports = new Array(importCount)
+    const exports = new Array(exportCount)
+    let i = 4
+    for (let j = 0; j < importCount; j++, i += 5) {
+        const t = buffer[i], s = buffer[i + 1], e = buffer[i + 2], ss = buffer[i + 3], se = buffer[i + 4]
+        let n, d
+        if (t === 1) {
+            n = source.slice(s, e)
+            d = -1
+        } else if (t === 2) {
+            n = stringLiteral(source.slice(s, e).trim())
+            // the paren right before the specifier expression
+            d = s - 1
+        } else {
+            d = -2
+        }
+        imports[j] = { n, t, s, e, ss, se, d, a: -1 }
+    }
+    for (let j = 0; j < exportCount; j++, i += 4) {
+        const s = buffer[i], e = buffer[i + 1], ls = buffer[i + 2], le = buffer[i + 3]
+        exports[j] = ls === NO_LOCAL_NAME
+            ? { n: source.slice(s, e), s, e, ln: undefined, ls: -1, le: -1 }
+            : { n: source.slice(s, e), s, e, ln: source.slice(ls, le), ls, le }
+    }
+    return [imports, exports, facade === 1, hasModuleSyntax === 1]
+}
+
+function stringLiteral(expression) {
+    const quote = expression[0]
+    if ((quote === "'" || quote === '"') && expression.length >= 2 && expression.endsWith(quote)) {
+        const contents = expression.slice(1, -1)
+        if (!contents.includes(quote)) return contents
+    }
+    return undefined
+}
@@ -0,0 +1,67 @@
+/*
+ * Turns the `--target web` output of wasm-pack in the given directory into a module
+ * that can be initialized synchronously, by inlining the wasm binary as base64.
+ *
+ * Usage: node js/inline_wasm.mjs wasm_sync
+ */
+import fs from 'fs';
+import path from 'path';
+
+const dir = process.argv[2];
+if (!dir) {
+	console.error('usage: node js/inline_wasm.mjs <wasm-pack output directory>');
+	process.exit(1);
+}
+
+const name = 'es_module_lexer';
+const wasm = fs.readFileSync(path.join(dir, `${name}_bg.wasm`));
+
+fs.writeFileSync(path.join(dir, `${name}_sync.js`), `\
+import { initSync as initSyncWithModule } from './${name}.js';
+
+export * from './${name}.js';
+
+const base64 = '${wasm.toString('base64')}';
+
+let initialized = false;
+
+/**
+ * Instantiates the inlined wasm binary synchronously, after which all other functions can be called.
+ * Calling it again does nothing.
+ */
+export function initSync() {
+	if (initialized) return;
+	const bytes = typeof Buffer !== 'undefined'
+		? Buffer.from(base64, 'base64')
+		: Uint8Array.from(atob(base64), c => c.charCodeAt(0));
+	initSyncWithModule({ module: bytes });
+	initialized = true;
+}
+`);
+
+fs.writeFileSync(path.join(dir, `${name}_sync.d.ts`), `\
+export * from './${name}.js';
+
+/**
+ * Instantiates the inlined wasm binary synchronously, after which all other functions can be called.
+ * Calling it again does nothing.
+ */
+export function initSync(): void;
+`);
+
+const packageJson = path.join(dir, 'package.json');
+const pkg = JSON.parse(fs.readFileSync(packageJson, 'utf8'));
+pkg.type = 'module';
+pkg.main = pkg.module = `${name}_sync.js`;
+pkg.types = `${name}_sync.d.ts`;
+// the binary is inlined, so only the glue, the inline JS snippets it imports
+// and the sync entry point are needed
+pkg.files = [
+	`${name}.js`, `${name}.d.ts`, `${name}_sync.js`, `${name}_sync.d.ts`, 'snippets', 'flat.js', 'flat.d.ts',
+];
+fs.writeFileSync(packageJson, JSON.stringify(pkg, null, 2) + '\n');
+
+fs.rmSync(path.join(dir, `${name}_bg.wasm`));
+fs.rmSync(path.join(dir, `${name}_bg.wasm.d.ts`), { force: true });
+
+console.log(`inlined ${wasm.byteLength} bytes of wasm into ${path.join(dir, `${name}_sync.js`)}`);
@@ -0,0 +1,134 @@
+//! Classification of source bytes through a 256-entry table, which drives the dispatch
+//! of the lexer and the predicates on single bytes.
+//!
+//! Bytes outside of ASCII are only ever part of identifiers, strings, comments and the like,
+//! so they are all classified as identifier characters, including the bytes of non-ASCII
+//! whitespace and line terminators.
+
+/// The class of a byte, with one class per byte that starts a different kind of token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ByteClass {
+    /// Spaces, tabs and line terminators.
+    Whitespace,
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+    SingleQuote,
+    DoubleQuote,
+    Backtick,
+    Slash,
+    Dot,
+    Semicolon,
+    Digit,
+    /// Letters, `_`, `$`, `\` (of a unicode escape) and all non-ASCII bytes.
+    Identifier,
+    /// The `#` of a private name, which only starts an identifier.
+    Hash,
+    /// The other punctuator characters, `!%&*+,-:<=>?[]^|~`.
+    Punctuator,
+    /// Any other byte, like control characters or `@`.
+    Other,
+}
+
+static BYTE_CLASSES: [ByteClass; 256] = byte_classes();
+
+const fn byte_classes() -> [ByteClass; 256] {
+    let mut classes = [ByteClass::Other; 256];
+    let mut ch = 0;
+    while ch < 256 {
+        classes[ch] = match ch as u8 {
+            b' ' | 9..=13 => ByteClass::Whitespace,
+            b'(' => ByteClass::OpenParen,
+            b')' => ByteClass::CloseParen,
+            b'{' => ByteClass::OpenBrace,
+            b'}' => ByteClass::CloseBrace,
+            b'\'' => ByteClass::SingleQuote,
+            b'"' => ByteClass::DoubleQuote,
+            b'`' => ByteClass::Backtick,
+            b'/' => ByteClass::Slash,
+            b'.' => ByteClass::Dot,
+            b';' => ByteClass::Semicolon,
+            b'0'..=b'9' => ByteClass::Digit,
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' | b'$' | b'\\' | 0x80..=0xff => ByteClass::Identifier,
+            b'#' => ByteClass::Hash,
+            b'!' | b'%' | b'&' | b'*' | b'+' | b',' | b'-' | b':' | b'<' | b'=' | b'>' | b'?'
+            | b'[' | b']' | b'^' | b'|' | b'~' => ByteClass::Punctuator,
+            _ => ByteClass::Other,
+        };
+        ch += 1;
+    }
+    classes
+}
+
+#[inline]
+pub(crate) fn byte_class(ch: u8) -> ByteClass {
+    BYTE_CLASSES[ch as usize]
+}
+
+#[inline]
+pub(crate) fn is_br_or_ws(ch: u8) -> bool {
+    byte_class(ch) == ByteClass::Whitespace
+}
+
+/// Whether the byte can end a punctuator, which is one of `!%&()*+,-./:;<=>?[]^{}|~`.
+#[inline]
+pub(crate) fn is_punctuator(ch: u8) -> bool {
+    matches!(
+        byte_class(ch),
+        ByteClass::OpenParen
+            | ByteClass::CloseParen
+            | ByteClass::OpenBrace
+            | ByteClass::CloseBrace
+            | ByteClass::Slash
+            | ByteClass::Dot
+            | ByteClass::Semicolon
+            | ByteClass::Punctuator
+    )
+}
+
+#[inline]
+pub(crate) fn is_identifier_start(ch: u8) -> bool {
+    matches!(byte_class(ch), ByteClass::Identifier | ByteClass::Hash)
+}
+
+#[inline]
+pub(crate) fn is_identifier_char(ch: u8) -> bool {
+    matches!(byte_class(ch), ByteClass::Identifier | ByteClass::Digit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predicates() {
+        for ch in 0..=255u8 {
+            let c = ch as char;
+            assert_eq!(
+                is_br_or_ws(ch),
+                c == ' ' || ('\t'..='\r').contains(&c),
+                "{}",
+                ch
+            );
+            assert_eq!(
+                is_punctuator(ch),
+                "!%&()*+,-./:;<=>?[]^{}|~".contains(c),
+                "{}",
+                ch
+            );
+            assert_eq!(
+                is_identifier_char(ch),
+                c.is_ascii_alphanumeric() || "_$\\".contains(c) || ch >= 0x80,
+                "{}",
+                ch
+            );
+            assert_eq!(
+                is_identifier_start(ch),
+                c.is_ascii_alphabetic() || "_$\\#".contains(c) || ch >= 0x80,
+                "{}",
+                ch
+            );
+        }
+    }
+}
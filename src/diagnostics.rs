@@ -0,0 +1,449 @@
+//! Human readable rendering of [`ParseError`]s: the offending source with a few lines of context,
+//! the error range underlined, related locations labelled and an optional hint.
+//!
+//! With the `miette` feature `ParseError` implements `miette::Diagnostic`, and with the
+//! `codespan-reporting` feature [`codespan_diagnostic`] converts it for `codespan-reporting`.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::Write;
+use core::ops::Range;
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::line_index::{ColumnUnit, LineIndex};
+
+const TAB_WIDTH: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Whether to colour the output with ANSI escape codes.
+    pub color: bool,
+    /// The number of source lines shown before and after the error.
+    pub context_lines: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            color: false,
+            context_lines: 2,
+        }
+    }
+}
+
+/// Renders the error as plain text with the default options.
+pub fn pretty_error(src: &str, err: &ParseError) -> String {
+    render(src, err, &RenderOptions::default())
+}
+
+/// Renders the error against the source it was produced from.
+///
+/// Never panics, even when the error does not point into `src`.
+pub fn render(src: &str, err: &ParseError, options: &RenderOptions) -> String {
+    let style = Style::new(options.color);
+    let lines = LineIndex::new(src);
+
+    let primary = clamp_range(src, &err.range);
+    let primary_position = lines.line_col(primary.start, ColumnUnit::Bytes);
+    let primary_line = primary_position.line;
+    let mut annotations = vec![Annotation::new(
+        src,
+        &lines,
+        primary.clone(),
+        primary_label(err),
+        true,
+    )];
+    if let Some(secondary) = &err.secondary {
+        let range = clamp_range(src, &secondary.range);
+        annotations.push(Annotation::new(
+            src,
+            &lines,
+            range,
+            secondary.message,
+            false,
+        ));
+    }
+
+    let mut shown_lines: Vec<usize> = (primary_line.saturating_sub(options.context_lines)
+        ..=primary_line
+            .saturating_add(options.context_lines)
+            .min(lines.line_count() - 1))
+        .collect();
+    for annotation in &annotations {
+        if !shown_lines.contains(&annotation.line) {
+            shown_lines.push(annotation.line);
+        }
+    }
+    shown_lines.sort_unstable();
+
+    let gutter_width = (shown_lines.last().copied().unwrap_or(0) + 1)
+        .to_string()
+        .len();
+    let empty_gutter = style.gutter(&format!("{:width$} |", "", width = gutter_width));
+
+    let mut output = String::new();
+    let _ = writeln!(output, "{}: {}", style.error("error"), err.message());
+    // the column counts bytes like `ParseError::col`, only the underlines use display widths
+    let _ = writeln!(
+        output,
+        "{} {}:{}",
+        style.gutter(&format!("{:width$}-->", "", width = gutter_width)),
+        primary_line + 1,
+        primary_position.col + 1
+    );
+    let _ = writeln!(output, "{}", empty_gutter);
+
+    let mut previous_line = None;
+    for &line in &shown_lines {
+        if let Some(previous_line) = previous_line {
+            if line > previous_line + 1 {
+                let _ = writeln!(output, "{}", style.gutter("..."));
+            }
+        }
+        previous_line = Some(line);
+
+        let line_number = format!("{:width$} |", line + 1, width = gutter_width);
+        let text = expand_tabs(&src[line_range(&lines, line)]);
+        let _ = writeln!(output, "{} {}", style.gutter(&line_number), text.trim_end());
+
+        for annotation in annotations.iter().filter(|a| a.line == line) {
+            let marker = if annotation.primary { "^" } else { "-" };
+            let underline = format!(
+                "{}{} {}",
+                " ".repeat(annotation.column),
+                marker.repeat(annotation.width),
+                annotation.message
+            );
+            let underline = if annotation.primary {
+                style.error(&underline)
+            } else {
+                style.secondary(&underline)
+            };
+            let _ = writeln!(output, "{} {}", empty_gutter, underline);
+        }
+    }
+
+    if let Some(hint) = hint(err.kind) {
+        let _ = writeln!(output, "{}", empty_gutter);
+        let _ = writeln!(
+            output,
+            "{} {}: {}",
+            style.gutter(&format!("{:width$} =", "", width = gutter_width)),
+            style.bold("help"),
+            hint
+        );
+    }
+
+    output
+}
+
+/// A hint on how to fix errors of the given kind.
+pub fn hint(kind: ParseErrorKind) -> Option<&'static str> {
+    match kind {
+        ParseErrorKind::UnterminatedString => {
+            Some("strings cannot span lines, use a template literal or escape the line break")
+        }
+        ParseErrorKind::UnterminatedRegex => {
+            Some("a `/` in a regular expression pattern must be escaped as `\\/`")
+        }
+        ParseErrorKind::UnbalancedBracket => {
+            Some("check for a missing or extra bracket in the surrounding code")
+        }
+        ParseErrorKind::InvalidImport => {
+            Some("import statements take the form `import ... from 'specifier'`")
+        }
+        ParseErrorKind::InvalidExport => {
+            Some("re-exports take the form `export ... from 'specifier'`")
+        }
+        ParseErrorKind::UnterminatedTemplate
+        | ParseErrorKind::UnterminatedBlockComment
+        | ParseErrorKind::UnexpectedEndOfInput
+        | ParseErrorKind::InvalidUtf8
+        | ParseErrorKind::LimitExceeded(_) => None,
+    }
+}
+
+fn primary_label(err: &ParseError) -> &'static str {
+    match err.expected {
+        [] => err.kind.message(),
+        [")"] => "expected `)`",
+        ["}"] => "expected `}`",
+        ["]"] => "expected `]`",
+        ["/"] => "expected `/`",
+        ["`"] => "expected `` ` ``",
+        ["'"] => "expected `'`",
+        ["\""] => "expected `\"`",
+        ["*/"] => "expected `*/`",
+        _ => err.kind.message(),
+    }
+}
+
+/// An underlined range within a single line.
+struct Annotation {
+    line: usize,
+    column: usize,
+    width: usize,
+    message: &'static str,
+    primary: bool,
+}
+
+impl Annotation {
+    fn new(
+        src: &str,
+        lines: &LineIndex,
+        range: Range<usize>,
+        message: &'static str,
+        primary: bool,
+    ) -> Annotation {
+        let line = lines.line_col(range.start, ColumnUnit::Bytes).line;
+        let Range {
+            start: line_start,
+            end: line_end,
+        } = line_range(lines, line);
+        // ranges spanning several lines are underlined up to the end of their first line
+        let end = range.end.min(line_end).max(range.start);
+        Annotation {
+            line,
+            column: display_width(&src[line_start..range.start]),
+            width: display_width(&src[range.start..end]).max(1),
+            message,
+            primary,
+        }
+    }
+}
+
+fn line_range(lines: &LineIndex, line: usize) -> Range<usize> {
+    lines.line_range(line).unwrap_or(0..0)
+}
+
+/// Clamps the range to the source and to character boundaries.
+fn clamp_range(src: &str, range: &Range<usize>) -> Range<usize> {
+    let start = floor_char_boundary(src, range.start);
+    let end = floor_char_boundary(src, range.end).max(start);
+    start..end
+}
+
+fn floor_char_boundary(src: &str, mut offset: usize) -> usize {
+    offset = offset.min(src.len());
+    while !src.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|ch| if ch == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn new(color: bool) -> Style {
+        Style { color }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn error(&self, text: &str) -> String {
+        self.paint("1;31", text)
+    }
+
+    fn secondary(&self, text: &str) -> String {
+        self.paint("1;36", text)
+    }
+
+    fn gutter(&self, text: &str) -> String {
+        self.paint("1;34", text)
+    }
+
+    fn bold(&self, text: &str) -> String {
+        self.paint("1", text)
+    }
+}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for ParseError {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(format!("{:?}", self.kind)))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        hint(self.kind).map(|hint| Box::new(hint) as Box<dyn std::fmt::Display>)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let primary = miette::LabeledSpan::new_primary_with_span(
+            Some(primary_label(self).to_string()),
+            self.range.clone(),
+        );
+        let secondary = self.secondary.iter().map(|label| {
+            miette::LabeledSpan::new_with_span(Some(label.message.to_string()), label.range.clone())
+        });
+        Some(Box::new(std::iter::once(primary).chain(secondary)))
+    }
+}
+
+/// Converts the error to a `codespan-reporting` diagnostic for the file with the given id.
+#[cfg(feature = "codespan-reporting")]
+pub fn codespan_diagnostic<FileId: Copy>(
+    err: &ParseError,
+    file_id: FileId,
+) -> codespan_reporting::diagnostic::Diagnostic<FileId> {
+    use codespan_reporting::diagnostic::{Diagnostic, Label};
+
+    let mut labels =
+        vec![Label::primary(file_id, err.range.clone()).with_message(primary_label(err))];
+    if let Some(secondary) = &err.secondary {
+        labels.push(
+            Label::secondary(file_id, secondary.range.clone()).with_message(secondary.message),
+        );
+    }
+    Diagnostic::error()
+        .with_message(err.message())
+        .with_code(format!("{:?}", err.kind))
+        .with_labels(labels)
+        .with_notes(hint(err.kind).map(str::to_string).into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn underlines_error_range() {
+        let source = "import a from './a.js';\nconst s = 'abc\nexport { s };";
+        let err = parse(source).unwrap_err();
+        let output = pretty_error(source, &err);
+        let expected = [
+            "error: unterminated string, expected `'`",
+            " --> 2:11",
+            "  |",
+            "1 | import a from './a.js';",
+            "2 | const s = 'abc",
+            "  |           ^^^^ expected `'`",
+            "3 | export { s };",
+            "  |",
+            "  = help: strings cannot span lines, use a template literal or escape the line break",
+        ];
+        assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn header_matches_error_position() {
+        let source = "é = 'abc";
+        let err = parse(source).unwrap_err();
+        let output = pretty_error(source, &err);
+        assert_eq!(err.to_string().split_whitespace().last(), Some("1:6"));
+        assert!(output.contains(" --> 1:6\n"));
+        assert!(output.contains("1 | é = 'abc\n  |     ^^^^ "));
+    }
+
+    #[test]
+    fn secondary_labels() {
+        let source = "f(a,\n  b\n}";
+        let err = parse(source).unwrap_err();
+        let output = render(
+            source,
+            &err,
+            &RenderOptions {
+                color: false,
+                context_lines: 0,
+            },
+        );
+        assert!(output.contains("1 | f(a,\n  |  - `(` opened here\n"));
+        assert!(output.contains("...\n3 | }\n  | ^ expected `)`\n"));
+    }
+
+    #[test]
+    fn colors() {
+        let err = parse("'a").unwrap_err();
+        let output = render(
+            "'a",
+            &err,
+            &RenderOptions {
+                color: true,
+                context_lines: 2,
+            },
+        );
+        assert!(output.starts_with("\x1b[1;31merror\x1b[0m: "));
+        assert!(!pretty_error("'a", &err).contains('\x1b'));
+    }
+
+    #[test]
+    fn edge_inputs() {
+        let sources = ["", "a", "\n\n", "a\r\nb", "é€\t'"];
+        let ranges = [
+            0..0,
+            0..1,
+            1..3,
+            // reversed
+            Range { start: 3, end: 1 },
+            2..100,
+            42..42,
+            usize::MAX - 1..usize::MAX,
+        ];
+        for source in sources.iter() {
+            for range in ranges.iter() {
+                #[allow(deprecated)]
+                let err = ParseError {
+                    idx: range.start,
+                    line: 0,
+                    col: 0,
+                    kind: ParseErrorKind::UnexpectedEndOfInput,
+                    range: range.clone(),
+                    expected: &[],
+                    secondary: None,
+                    msg: None,
+                }
+                .with_secondary(range.end..range.start, "here");
+                render(source, &err, &RenderOptions::default());
+                render(
+                    source,
+                    &err,
+                    &RenderOptions {
+                        color: true,
+                        context_lines: usize::MAX,
+                    },
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "miette")]
+    #[test]
+    fn miette_labels() {
+        use miette::Diagnostic;
+
+        let err = parse("f(a}").unwrap_err();
+        let labels: Vec<_> = err.labels().unwrap().collect();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[0].offset(), 3);
+        assert_eq!(labels[1].label(), Some("`(` opened here"));
+    }
+
+    #[cfg(feature = "codespan-reporting")]
+    #[test]
+    fn codespan_labels() {
+        let err = parse("f(a}").unwrap_err();
+        let diagnostic = codespan_diagnostic(&err, ());
+        assert_eq!(diagnostic.labels.len(), 2);
+        assert_eq!(diagnostic.labels[0].range, 3..4);
+        assert_eq!(diagnostic.notes.len(), 1);
+    }
+}
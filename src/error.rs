@@ -1,91 +1,212 @@
-use std::fmt::{Display, Formatter};
-use std::{fmt, str};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use core::ops::Range;
 
-#[derive(Debug)]
+#[cfg(feature = "wasm")]
+use tsify::Tsify;
+
+use crate::limits::Limit;
+use crate::line_index::{locate, ColumnUnit, LineCol, LineIndex};
+
+/// What went wrong while lexing a module. More kinds may be added,
+/// so matching on it needs a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize),
+    serde(tag = "kind", content = "limit")
+)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    UnterminatedString,
+    UnterminatedTemplate,
+    UnterminatedRegex,
+    UnterminatedBlockComment,
+    /// A closing brace or paren without a matching opening one, or the other way around.
+    UnbalancedBracket,
+    UnexpectedEndOfInput,
+    InvalidImport,
+    InvalidExport,
+    /// A module specifier or export name that is not valid UTF-8.
+    InvalidUtf8,
+    /// One of the configured [`ParseLimits`](crate::ParseLimits) was exceeded.
+    LimitExceeded(Limit),
+}
+
+impl ParseErrorKind {
+    pub fn message(self) -> &'static str {
+        match self {
+            ParseErrorKind::UnterminatedString => "unterminated string",
+            ParseErrorKind::UnterminatedTemplate => "unterminated template string",
+            ParseErrorKind::UnterminatedRegex => "unterminated regular expression",
+            ParseErrorKind::UnterminatedBlockComment => "unterminated block comment",
+            ParseErrorKind::UnbalancedBracket => "unbalanced brace or paren",
+            ParseErrorKind::UnexpectedEndOfInput => "unexpected end of input",
+            ParseErrorKind::InvalidImport => "invalid import syntax",
+            ParseErrorKind::InvalidExport => "invalid export syntax",
+            ParseErrorKind::InvalidUtf8 => "invalid UTF-8",
+            ParseErrorKind::LimitExceeded(Limit::NestingDepth) => "maximum nesting depth exceeded",
+            ParseErrorKind::LimitExceeded(Limit::SourceBytes) => "maximum source size exceeded",
+            ParseErrorKind::LimitExceeded(Limit::Imports) => "maximum number of imports exceeded",
+            ParseErrorKind::LimitExceeded(Limit::Records) => "maximum number of records exceeded",
+        }
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ParseError {
+    /// Index of the start of `range`.
     pub idx: usize,
     pub line: usize,
     pub col: usize,
+    pub kind: ParseErrorKind,
+    /// Byte range of the offending source, e.g. an unterminated string from its opening quote.
+    pub range: Range<usize>,
+    /// The token(s) that would have been valid at the end of `range`.
+    pub expected: &'static [&'static str],
+    /// A related location, e.g. where an unmatched bracket was opened.
+    pub secondary: Option<Label>,
+    /// The message of `kind`, kept while code moves off it.
+    #[deprecated(note = "use `kind`, `message` or the `Display` implementation")]
     pub msg: Option<&'static str>,
 }
 
+/// A labelled range of the source related to an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub range: Range<usize>,
+    pub message: &'static str,
+}
+
 impl ParseError {
-    pub fn from_source_and_index<'a, T: AsRef<[u8]>>(source: T, idx: usize) -> ParseError {
-        ParseError::create(source, idx, None)
+    /// Creates an error for the given range of the source.
+    ///
+    /// The one based line and column are counted in bytes, so the source does not need to be
+    /// valid UTF-8. They are both `0` when the range starts past the end of the source.
+    pub fn new<T: AsRef<[u8]>>(source: T, kind: ParseErrorKind, range: Range<usize>) -> ParseError {
+        let source = source.as_ref();
+        let mut err = ParseError::unlocated(kind, range);
+        if err.idx <= source.len() {
+            err.set_position(locate(source, err.idx));
+        }
+        err
+    }
+
+    /// An error whose line and column are `0` until it is located in the source,
+    /// which the lexer only does for the errors it reports.
+    #[allow(deprecated)]
+    pub(crate) fn unlocated(kind: ParseErrorKind, range: Range<usize>) -> ParseError {
+        ParseError {
+            idx: range.start,
+            line: 0,
+            col: 0,
+            kind,
+            range,
+            expected: &[],
+            secondary: None,
+            msg: Some(kind.message()),
+        }
+    }
+
+    /// Sets the line and column by scanning the source, for a single error.
+    pub(crate) fn locate(&mut self, source: &[u8]) {
+        if self.idx <= source.len() {
+            self.set_position(locate(source, self.idx));
+        }
+    }
+
+    /// Sets the line and column from an index of the source, for one of many errors.
+    pub(crate) fn locate_with(&mut self, index: &LineIndex) {
+        if self.idx <= index.source_len() {
+            self.set_position(index.line_col(self.idx, ColumnUnit::Bytes));
+        }
     }
 
-    pub fn from_source_index_and_msg<'a, T: AsRef<[u8]>>(
+    fn set_position(&mut self, position: LineCol) {
+        self.line = position.line + 1;
+        self.col = position.col + 1;
+    }
+
+    /// Creates an error at the given index, with a kind guessed from it.
+    #[deprecated(note = "use `ParseError::new` with the kind and range of the error")]
+    pub fn from_source_and_index<T: AsRef<[u8]>>(source: T, idx: usize) -> ParseError {
+        let source = source.as_ref();
+        // errors without a kind were mostly unbalanced brackets, or reaching the end of the source
+        let kind = if idx < source.len() {
+            ParseErrorKind::UnbalancedBracket
+        } else {
+            ParseErrorKind::UnexpectedEndOfInput
+        };
+        ParseError::new(source, kind, idx..idx + 1)
+    }
+
+    /// Creates an error at the given index like [`ParseError::from_source_and_index`],
+    /// ignoring the message, which is now given by the kind.
+    #[deprecated(note = "use `ParseError::new` with the kind and range of the error")]
+    #[allow(deprecated)]
+    pub fn from_source_index_and_msg<T: AsRef<[u8]>>(
         source: T,
         idx: usize,
-        msg: &'static str,
+        _msg: &'static str,
     ) -> ParseError {
-        ParseError::create(source, idx, Some(msg))
-    }
-
-    fn create<'a, T: AsRef<[u8]>>(source: T, idx: usize, msg: Option<&'static str>) -> ParseError {
-        let substring = match str::from_utf8(source.as_ref())
-            .ok()
-            .and_then(|s| s.get(0..idx + 1))
-        {
-            None => {
-                return ParseError {
-                    idx,
-                    line: 0,
-                    col: 0,
-                    msg,
-                }
-            }
-            Some(s) => s,
-        };
+        ParseError::from_source_and_index(source, idx)
+    }
 
-        let (line, last_line) = substring
-            .lines()
-            .fold((0, None), |(count, _), line| (count + 1, Some(line)));
+    pub fn with_expected(mut self, expected: &'static [&'static str]) -> ParseError {
+        self.expected = expected;
+        self
+    }
 
-        ParseError {
-            idx,
-            line,
-            col: last_line.unwrap().len(),
-            msg,
+    pub fn with_secondary(mut self, range: Range<usize>, message: &'static str) -> ParseError {
+        self.secondary = Some(Label { range, message });
+        self
+    }
+
+    /// The zero based position of the error in the given unit.
+    pub fn position(&self, index: &LineIndex, unit: ColumnUnit) -> LineCol {
+        index.line_col(self.range.start, unit)
+    }
+
+    /// The error message, including the expected tokens if any.
+    pub fn message(&self) -> String {
+        match self.expected {
+            [] => self.kind.message().to_string(),
+            expected => format!(
+                "{}, expected {}",
+                self.kind.message(),
+                expected
+                    .iter()
+                    .map(|token| format!("`{}`", token))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
         }
     }
 }
 
+// `core::error::Error` would need Rust 1.81
+#[cfg(feature = "std")]
 impl std::error::Error for ParseError {}
 
 impl Display for ParseError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "ParseError at {}:{}", self.line, self.col)
+        write!(f, "{} at {}:{}", self.message(), self.line, self.col)
     }
 }
 
-pub fn pretty_error(src: &str, err: &ParseError) -> String {
-    let msg = err.msg.unwrap_or("unexpected token");
-    let line_number = err.line.to_string();
-    let pad_width = line_number.len() + 1;
-    let line_code = src
-        .lines()
-        .skip(err.line - 1)
-        .next()
-        .expect("line is not in source");
-
-    let mut output = format!("ParseError: at {}:{}\n", err.line, err.col);
-    output += &format!("{:width$}|\n", "", width = pad_width);
-    output += &format!("{:width$}|{}\n", line_number, line_code, width = pad_width);
-    output += &format!(
-        "{:width$}|{}^ {}\n",
-        "",
-        " ".repeat(err.col - 1),
-        msg,
-        width = pad_width
-    );
-
-    output
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::error::ParseError;
+    use crate::error::{ParseError, ParseErrorKind};
 
     #[test]
     fn basic() {
@@ -97,16 +218,49 @@ mod tests {
 		"#;
 
         let err_idx = source.find('{').unwrap();
-        let err = ParseError::from_source_and_index(source, err_idx);
+        let err = ParseError::new(source, ParseErrorKind::InvalidImport, err_idx..err_idx + 1);
         assert_eq!(err.line, 3);
         assert_eq!(err.col, 11);
     }
 
+    #[test]
+    fn line_terminators() {
+        let source = "a\rb\r\nc\u{2028}d";
+        let err_idx = source.find('d').unwrap();
+        let err = ParseError::new(source, ParseErrorKind::InvalidImport, err_idx..err_idx + 1);
+        assert_eq!(err.line, 4);
+        assert_eq!(err.col, 1);
+    }
+
     #[test]
     fn empty_source() {
         let source = "";
-        let err = ParseError::from_source_and_index(source, 42);
+        let err = ParseError::new(source, ParseErrorKind::UnexpectedEndOfInput, 42..42);
         assert_eq!(err.line, 0);
         assert_eq!(err.col, 0);
     }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_constructors() {
+        let err = ParseError::from_source_and_index("f(}", 2);
+        assert_eq!(
+            (err.kind, err.range.clone(), err.line, err.col),
+            (ParseErrorKind::UnbalancedBracket, 2..3, 1, 3)
+        );
+        assert_eq!(err.msg, Some("unbalanced brace or paren"));
+        let err = ParseError::from_source_index_and_msg("f(", 2, "unexpected end");
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEndOfInput);
+        assert!(err.to_string().starts_with(err.msg.unwrap()));
+    }
+
+    #[test]
+    fn message() {
+        let source = "'a";
+        let err = ParseError::new(source, ParseErrorKind::UnterminatedString, 0..2);
+        assert_eq!(err.message(), "unterminated string");
+        let err = err.with_expected(&["'"]);
+        assert_eq!(err.message(), "unterminated string, expected `'`");
+        assert_eq!(err.to_string(), "unterminated string, expected `'` at 1:1");
+    }
 }
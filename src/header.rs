@@ -0,0 +1,94 @@
+//! Lexing only the header of a module: the import statements, re-exports and directives
+//! it starts with, which is where its static dependencies usually are.
+
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+use crate::token::Keyword;
+use crate::{
+    comment_whitespace, double_quote_string, is_identifier_char, single_quote_string,
+    ModuleVisitor, ParseState, StaticImport,
+};
+
+/// The static imports at the start of a module, see [`parse_header`].
+///
+/// [`parse_header`]: crate::parse_header
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ModuleHeader {
+    /// The static imports of the header, including those of re-exports like `export * from './a.js'`.
+    pub imports: Vec<StaticImport>,
+    /// Whether lexing stopped at a statement ending the header, after which the module
+    /// may contain more imports. `false` when the whole source is the header.
+    pub may_contain_more: bool,
+}
+
+/// Collects the static imports of the header.
+pub(crate) struct HeaderBuilder<'a> {
+    pub(crate) imports: &'a mut Vec<StaticImport>,
+}
+
+impl ModuleVisitor for HeaderBuilder<'_> {
+    fn on_static_import(&mut self, import: StaticImport) -> ControlFlow<()> {
+        self.imports.push(import);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Whether the top-level token at the current index ends the header,
+/// checked before it is lexed.
+///
+/// Import and export statements are not ended here, but only once lexing them
+/// did not report a static import, which also rules out exports of declarations.
+pub(crate) fn ends_header(state: &mut ParseState) -> bool {
+    let rest = &state.src[state.i..];
+    match rest[0] {
+        b' ' | 9..=13 | b';' => false,
+        b'/' => !matches!(rest.get(1), Some(b'/' | b'*')),
+        b'\'' | b'"' => !is_directive(state),
+        _ => {
+            let len = rest
+                .iter()
+                .position(|&ch| !is_identifier_char(ch))
+                .unwrap_or(rest.len());
+            match Keyword::from_identifier(&rest[..len]) {
+                // dynamic imports and `import.meta` are code
+                Some(Keyword::Import) => matches!(peek_after(state, len), '(' | '.'),
+                Some(Keyword::Export) => false,
+                _ => true,
+            }
+        }
+    }
+}
+
+/// Whether the string literal at the current index is a directive like `'use strict';`,
+/// that is whether it is followed by a `;` or the end of the line.
+fn is_directive(state: &mut ParseState) -> bool {
+    let start = state.i;
+    let terminated = if state.src[start] == b'\'' {
+        single_quote_string(state).is_ok()
+    } else {
+        double_quote_string(state).is_ok()
+    };
+    let end = state.i + 1;
+    state.i = start;
+    if !terminated {
+        // the error is reported when the string is lexed
+        return true;
+    }
+    let next = state.src[end..]
+        .iter()
+        .find(|&&ch| ch != b' ' && ch != b'\t');
+    matches!(next, None | Some(b';' | b'\n' | b'\r'))
+}
+
+/// The next character after `len` bytes, skipping whitespace and comments,
+/// without consuming anything.
+fn peek_after(state: &mut ParseState, len: usize) -> char {
+    let start = state.i;
+    state.i += len;
+    // an unterminated comment is reported when it is lexed
+    let ch = comment_whitespace(state).unwrap_or('\0');
+    state.i = start;
+    ch
+}
@@ -1,17 +1,49 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 #[cfg(feature = "serde")]
 #[macro_use]
 extern crate serde;
 
-use std::ops::Range;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::{ControlFlow, Range};
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-pub use error::pretty_error;
-use error::ParseError;
-
+use byte_class::{
+    byte_class, is_br_or_ws, is_identifier_char, is_identifier_start, is_punctuator, ByteClass,
+};
+pub use diagnostics::pretty_error;
+pub use error::{Label, ParseError, ParseErrorKind};
+pub use header::ModuleHeader;
+pub use limits::{Limit, ParseLimits};
+pub use line_index::{ColumnUnit, LineCol, LineIndex};
+#[cfg(feature = "parallel")]
+pub use parallel::{parse_dir, parse_many, FileError};
+#[cfg(feature = "std")]
+pub use stream::parse_reader;
+pub use stream::StreamLexer;
+use token::{Keyword, Token, TokenHistory, TokenKind};
+
+mod byte_class;
+pub mod diagnostics;
 mod error;
+mod header;
+mod limits;
+mod line_index;
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(test)]
+mod recorder;
+mod stream;
+mod token;
 
+#[cfg(feature = "wasm")]
+mod wasm_compat;
+#[cfg(feature = "wasm")]
+mod wasm_flat;
 #[cfg(feature = "wasm")]
 mod wasm_types;
 
@@ -40,6 +72,13 @@ impl StaticImport {
     pub fn statement_range(&self) -> Range<usize> {
         self.statement_start..self.statement_end
     }
+
+    /// The module specifier in the source it was parsed from, failing if it is not valid UTF-8.
+    ///
+    /// Escape sequences in the specifier are not decoded.
+    pub fn module_specifier<'a>(&self, source: &'a [u8]) -> Result<&'a str, ParseError> {
+        decode(source, self.module_specifier_range())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -72,47 +111,286 @@ impl ImportMeta {
     }
 }
 
+/// An exported name. More fields may be added, so it can only be destructured with `..`.
 #[derive(Debug, Eq, PartialEq, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
 pub struct Export {
     pub start: usize,
     pub end: usize,
+    /// The range of the local binding that is exported, `None` for re-exports
+    /// and for `export default` of an expression.
+    pub local: Option<Range<usize>>,
 }
 
 impl Export {
     pub fn export_specifier_range(&self) -> Range<usize> {
-        return self.start..self.end;
+        self.start..self.end
+    }
+
+    /// The exported name in the source it was parsed from, failing if it is not valid UTF-8.
+    pub fn name<'a>(&self, source: &'a [u8]) -> Result<&'a str, ParseError> {
+        decode(source, self.export_specifier_range())
+    }
+
+    /// The local name in the source it was parsed from, see [`Export::local`].
+    pub fn local_name<'a>(&self, source: &'a [u8]) -> Result<Option<&'a str>, ParseError> {
+        self.local
+            .clone()
+            .map(|range| decode(source, range))
+            .transpose()
+    }
+}
+
+/// Decodes a range of the source as UTF-8, reporting the first invalid sequence as an error.
+fn decode(source: &[u8], range: Range<usize>) -> Result<&str, ParseError> {
+    let bytes = match source.get(range.clone()) {
+        Some(bytes) => bytes,
+        None => {
+            let len = source.len();
+            return Err(ParseError::new(
+                source,
+                ParseErrorKind::UnexpectedEndOfInput,
+                len..len,
+            ));
+        }
+    };
+    core::str::from_utf8(bytes).map_err(|err| {
+        let start = range.start + err.valid_up_to();
+        let end = err.error_len().map_or(range.end, |len| start + len);
+        ParseError::new(source, ParseErrorKind::InvalidUtf8, start..end)
+    })
+}
+
+/// A regular expression literal, e.g. `/[a-z]+/gi`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RegularExpression {
+    pub start: usize,
+    pub flags_start: usize,
+    pub end: usize,
+}
+
+impl RegularExpression {
+    /// The whole literal, from the opening slash up to and including the flags.
+    pub fn expression_range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// The pattern between the two slashes.
+    pub fn pattern_range(&self) -> Range<usize> {
+        self.start + 1..self.flags_start - 1
+    }
+
+    /// The flags following the closing slash (empty when there are none).
+    pub fn flags_range(&self) -> Range<usize> {
+        self.flags_start..self.end
     }
 }
 
+/// The records of a module. More of them may be added, so it can only be destructured with `..`.
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
 pub struct SourceAnalysis {
     pub imports: Vec<Import>,
     pub exports: Vec<Export>,
+    pub regular_expressions: Vec<RegularExpression>,
+    /// Whether the module consists only of import and re-export statements,
+    /// e.g. `export * from './a.js'`, without any code of its own.
+    pub facade: bool,
+    /// Whether the module contains import or export statements or `import.meta`.
+    pub has_module_syntax: bool,
+}
+
+/// The kind of bracket that opened a nesting level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenTokenKind {
+    Paren,
+    Block,
+    ObjectLiteral,
+    ClassBody,
+    TemplateExpression,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OpenToken {
+    kind: OpenTokenKind,
+    /// Index of the opening bracket.
+    start: usize,
+    /// The token preceding the opening bracket.
+    preceding: Option<Token>,
+    /// Whether the bracketed construct is an expression
+    /// (an object literal, or the body of a function or class expression).
+    expression: bool,
+}
+
+/// A function or class body expected at the next `{` on the given nesting depth.
+#[derive(Debug, Clone, Copy)]
+struct PendingBody {
+    depth: usize,
+    kind: OpenTokenKind,
+    expression: bool,
+}
+
+/// Receives the records of a source as the lexer finds them, see [`parse_with_visitor`].
+///
+/// Each callback can return [`ControlFlow::Break`] to stop lexing right away.
+/// Dynamic imports are reported once their closing paren is found,
+/// so after any imports nested in their specifier expression.
+pub trait ModuleVisitor {
+    fn on_static_import(&mut self, _import: StaticImport) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_dynamic_import(&mut self, _import: DynamicImport) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_import_meta(&mut self, _import: ImportMeta) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_export(&mut self, _export: Export) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_regular_expression(&mut self, _regex: RegularExpression) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// A record as passed to a [`ModuleVisitor`].
+#[derive(Debug, Clone)]
+enum Record {
+    StaticImport(StaticImport),
+    DynamicImport(DynamicImport),
+    ImportMeta(ImportMeta),
+    Export(Export),
+    RegularExpression(RegularExpression),
+}
+
+impl Record {
+    fn visit(self, visitor: &mut dyn ModuleVisitor) -> ControlFlow<()> {
+        match self {
+            Record::StaticImport(import) => visitor.on_static_import(import),
+            Record::DynamicImport(import) => visitor.on_dynamic_import(import),
+            Record::ImportMeta(import) => visitor.on_import_meta(import),
+            Record::Export(export) => visitor.on_export(export),
+            Record::RegularExpression(regex) => visitor.on_regular_expression(regex),
+        }
+    }
+}
+
+/// Collects the records into a [`SourceAnalysis`], in source order.
+struct AnalysisBuilder<'a> {
+    analysis: &'a mut SourceAnalysis,
+}
+
+impl Import {
+    fn statement_start(&self) -> usize {
+        match self {
+            Import::Static(import) => import.statement_start,
+            Import::Dynamic(import) => import.statement_start,
+            Import::Meta(import) => import.start,
+        }
+    }
+}
+
+impl AnalysisBuilder<'_> {
+    fn push_import(&mut self, import: Import) -> ControlFlow<()> {
+        let imports = &mut self.analysis.imports;
+        // dynamic imports arrive after the imports nested in them
+        let start = import.statement_start();
+        match imports.last() {
+            Some(last) if last.statement_start() > start => {
+                let idx = imports.partition_point(|import| import.statement_start() < start);
+                imports.insert(idx, import);
+            }
+            _ => imports.push(import),
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl ModuleVisitor for AnalysisBuilder<'_> {
+    fn on_static_import(&mut self, import: StaticImport) -> ControlFlow<()> {
+        self.push_import(Import::Static(import))
+    }
+
+    fn on_dynamic_import(&mut self, import: DynamicImport) -> ControlFlow<()> {
+        self.push_import(Import::Dynamic(import))
+    }
+
+    fn on_import_meta(&mut self, import: ImportMeta) -> ControlFlow<()> {
+        self.push_import(Import::Meta(import))
+    }
+
+    fn on_export(&mut self, export: Export) -> ControlFlow<()> {
+        self.analysis.exports.push(export);
+        ControlFlow::Continue(())
+    }
+
+    fn on_regular_expression(&mut self, regex: RegularExpression) -> ControlFlow<()> {
+        self.analysis.regular_expressions.push(regex);
+        ControlFlow::Continue(())
+    }
+}
+
+/// The flags of [`SourceAnalysis`], which are only known once the whole source is lexed.
+#[derive(Debug, Clone, Copy)]
+struct ModuleFlags {
+    facade: bool,
+    has_module_syntax: bool,
+    /// Whether lexing only the header stopped at a statement after it.
+    header_ended: bool,
 }
 
-#[derive(Debug)]
 struct ParseState<'a> {
     src: &'a [u8],
     i: usize,
-    template_stack: Vec<usize>,
-    open_token_index_stack: Vec<usize>,
-    template_depth: Option<usize>,
-    open_token_depth: usize,
-    last_token_index: usize,
-    next_brace_is_class: bool,
-    open_class_index_stack: Vec<bool>,
-    last_dynamic_import: Option<usize>,
-    analysis: SourceAnalysis,
+    open_token_stack: Vec<OpenToken>,
+    tokens: TokenHistory,
+    pending_body: Option<PendingBody>,
+    /// Open dynamic imports with the open token depth before their paren.
+    dynamic_import_stack: Vec<(usize, DynamicImport)>,
+    limits: ParseLimits,
+    /// Whether to stop at the end of the header, see [`parse_header`].
+    header_only: bool,
+    /// The start of the import or export statement being lexed, from which recovering
+    /// from an error in it skips to the next statement.
+    statement_start: Option<usize>,
+    visitor: &'a mut dyn ModuleVisitor,
+    /// Records held back from the visitor until the token they were found in is complete,
+    /// when lexing a stream.
+    deferred: Option<&'a mut Vec<Record>>,
+    /// Whether the visitor stopped lexing.
+    stopped: bool,
+    /// The furthest index whitespace and comments were skipped to, also when peeking past them.
+    reached: usize,
+    /// The number of imports, and of imports and exports, reported so far,
+    /// including open dynamic imports.
+    imports: usize,
+    records: usize,
+    flags: ModuleFlags,
 }
 
+/// Parses the source, with all ranges in UTF-16 code units.
+///
+/// @throws {ParseError} When the source cannot be parsed or one of the limits in `options`
+/// is exceeded, with the error rendered like by `pretty_error` as its message.
 #[cfg(feature = "wasm")]
 #[wasm_bindgen(js_name = "parse")]
-pub fn parse_wasm(input: &str) -> Result<wasm_types::SourceAnalysis, JsValue> {
-    let output = parse(input).map_err(|err| JsValue::from(pretty_error(input, &err)))?;
+pub fn parse_wasm(
+    input: &str,
+    options: Option<wasm_types::ParseOptions>,
+) -> Result<wasm_types::SourceAnalysis, JsValue> {
+    let limits = options.map_or_else(ParseLimits::default, |options| options.limits());
+    let output =
+        parse_with_limits(input, limits).map_err(|err| wasm_types::error_to_js(input, &err))?;
 
     Ok(wasm_types::SourceAnalysis::from_imports_and_exports(
+        input,
         output.imports,
         output.exports,
     ))
@@ -121,274 +399,1109 @@ pub fn parse_wasm(input: &str) -> Result<wasm_types::SourceAnalysis, JsValue> {
     //     .map_err(|err| format!("failed to serialize parse output: {}", err.to_string()).into())
 }
 
+/// Parses the source into `[imports, exports, facade, hasModuleSyntax]` of plain objects,
+/// like `parse` of the `es-module-lexer` package, with offsets in UTF-16 code units.
+///
+/// @throws {CompatParseError} An `Error` with the UTF-16 offset of the error as its `idx`,
+/// also when one of the limits in `options` is exceeded.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = "parseCompat")]
+pub fn parse_compat_wasm(
+    input: &str,
+    name: Option<String>,
+    options: Option<wasm_types::ParseOptions>,
+) -> Result<wasm_compat::CompatResult, JsValue> {
+    let limits = options.map_or_else(ParseLimits::default, |options| options.limits());
+    let output = parse_with_limits(input, limits)
+        .map_err(|err| wasm_compat::error_to_js(input, name.as_deref(), &err))?;
+    Ok(wasm_compat::to_compat(input, &output))
+}
+
+/// Parses many sources in one call, like `parseCompat` for each of them with the matching name
+/// and the same options.
+///
+/// Returns an array with the result of each source, which is the `Error` it would throw
+/// if it could not be parsed.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = "parseMany")]
+pub fn parse_many_wasm(
+    sources: wasm_types::StringArray,
+    names: Option<wasm_types::NameArray>,
+    options: Option<wasm_types::ParseOptions>,
+) -> wasm_compat::CompatResults {
+    let limits = options.map_or_else(ParseLimits::default, |options| options.limits());
+    wasm_compat::parse_many(&sources, names.as_deref(), limits)
+}
+
+/// A view of `len` bytes of wasm memory for the UTF-8 source to be written into,
+/// e.g. with `TextEncoder.encodeInto`, before calling `parseSourceBuffer`.
+///
+/// The view must not be used after any other call into the module, which may move the buffer.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = "sourceBuffer")]
+pub fn source_buffer_wasm(len: usize) -> js_sys::Uint8Array {
+    wasm_flat::source_buffer(len)
+}
+
+/// Parses the first `len` bytes of the source buffer, returning a view of the results
+/// as a flat buffer of `u32`s in wasm memory, which `js/flat.js` decodes.
+///
+/// The view must not be used after any other call into the module.
+///
+/// @throws {CompatParseError} Like `parseCompat`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(js_name = "parseSourceBuffer")]
+pub fn parse_source_buffer_wasm(
+    len: usize,
+    name: Option<String>,
+    options: Option<wasm_types::ParseOptions>,
+) -> Result<js_sys::Uint32Array, JsValue> {
+    let limits = options.map_or_else(ParseLimits::default, |options| options.limits());
+    wasm_flat::parse_source_buffer(len, name.as_deref(), limits)
+}
+
 pub fn parse(input: &str) -> Result<SourceAnalysis, ParseError> {
-    let mut state = ParseState {
-        src: input.as_bytes(),
-        i: 0,
-        template_stack: Vec::<usize>::with_capacity(10),
-        open_token_index_stack: Vec::<usize>::with_capacity(50),
-        template_depth: None,
-        open_token_depth: 0,
-        last_token_index: usize::MAX,
-        open_class_index_stack: Vec::<bool>::with_capacity(10),
-        next_brace_is_class: false,
-        last_dynamic_import: None,
-        analysis: SourceAnalysis {
-            imports: Vec::with_capacity(20),
-            exports: Vec::with_capacity(20),
-        },
-    };
+    parse_with_limits(input, ParseLimits::default())
+}
+
+/// Parses raw source bytes, which do not need to be valid UTF-8.
+///
+/// Bytes outside of ASCII are never significant to the lexer, so invalid UTF-8 sequences
+/// (or Latin-1 text) are treated like any other non-ASCII characters: as part of the identifier,
+/// string, comment or other token they occur in. All offsets are byte offsets into `input`.
+/// Use [`StaticImport::module_specifier`] and [`Export::name`] to decode the results.
+pub fn parse_bytes(input: &[u8]) -> Result<SourceAnalysis, ParseError> {
+    let mut lexer = Lexer::new();
+    lexer.parse_bytes(input)?;
+    Ok(lexer.analysis)
+}
 
-    let mut first = true;
-    let mut skip_set_last_token = false;
-    let mut last_slash_was_division = false;
+/// Parses the source like [`parse`], failing once any of the given limits is exceeded.
+pub fn parse_with_limits(input: &str, limits: ParseLimits) -> Result<SourceAnalysis, ParseError> {
+    let mut lexer = Lexer::with_limits(limits);
+    lexer.parse(input)?;
+    Ok(lexer.analysis)
+}
 
-    let len = state.src.len();
-    while state.i < len - 1 {
-        if first {
-            first = false;
-        } else {
-            state.i += 1;
+/// Parses the source like [`parse`], but recovers from errors instead of stopping at the first one.
+///
+/// After an unterminated string or regular expression lexing resumes at the next line,
+/// and after invalid import or export syntax at the next statement,
+/// so that imports and exports around the broken region are still reported.
+/// Returns the analysis together with every error encountered.
+pub fn parse_with_recovery(input: &str) -> (SourceAnalysis, Vec<ParseError>) {
+    let mut lexer = Lexer::new();
+    lexer.parse_with_recovery(input);
+    (lexer.analysis, lexer.errors)
+}
+
+/// Parses the source like [`parse`], passing each record to the visitor
+/// instead of collecting them.
+///
+/// ```
+/// use core::ops::ControlFlow;
+/// use es_module_lexer::{ModuleVisitor, StaticImport};
+///
+/// struct BareSpecifiers<'a> {
+///     source: &'a str,
+///     count: usize,
+/// }
+///
+/// impl ModuleVisitor for BareSpecifiers<'_> {
+///     fn on_static_import(&mut self, import: StaticImport) -> ControlFlow<()> {
+///         let specifier = &self.source[import.module_specifier_range()];
+///         if !specifier.starts_with('.') && !specifier.starts_with('/') {
+///             self.count += 1;
+///         }
+///         ControlFlow::Continue(())
+///     }
+/// }
+///
+/// let source = "import a from 'a';\nimport b from './b.js';";
+/// let mut visitor = BareSpecifiers { source, count: 0 };
+/// es_module_lexer::parse_with_visitor(source, &mut visitor).unwrap();
+/// assert_eq!(visitor.count, 1);
+/// ```
+pub fn parse_with_visitor(input: &str, visitor: &mut dyn ModuleVisitor) -> Result<(), ParseError> {
+    Lexer::new().parse_with_visitor(input, visitor)
+}
+
+/// Lexes only the header of the module, the import statements, re-exports and directives
+/// it starts with, stopping at the first other statement.
+///
+/// This answers what a module imports statically without lexing the rest of it, although
+/// imports after the header are missed, which [`ModuleHeader::may_contain_more`] tells.
+/// Errors are only reported for the header.
+pub fn parse_header(input: &str) -> Result<ModuleHeader, ParseError> {
+    Lexer::new().parse_header(input)
+}
+
+/// A lexer that keeps its buffers between sources, to avoid allocating them for every parse.
+///
+/// The results of a parse borrow the lexer until the next one, which reuses their memory.
+///
+/// ```
+/// let mut lexer = es_module_lexer::Lexer::new();
+/// for source in ["import a from './a.js';", "export const b = 1;"].iter() {
+///     let analysis = lexer.parse(source).unwrap();
+///     println!("{} imports", analysis.imports.len());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Lexer {
+    limits: ParseLimits,
+    open_token_stack: Vec<OpenToken>,
+    dynamic_import_stack: Vec<(usize, DynamicImport)>,
+    analysis: SourceAnalysis,
+    errors: Vec<ParseError>,
+}
+
+impl Default for Lexer {
+    fn default() -> Lexer {
+        Lexer::new()
+    }
+}
+
+impl Lexer {
+    pub fn new() -> Lexer {
+        Lexer::with_limits(ParseLimits::default())
+    }
+
+    /// A lexer failing once any of the given limits is exceeded.
+    pub fn with_limits(limits: ParseLimits) -> Lexer {
+        Lexer {
+            limits,
+            open_token_stack: Vec::with_capacity(50),
+            dynamic_import_stack: Vec::with_capacity(10),
+            analysis: SourceAnalysis {
+                imports: Vec::with_capacity(20),
+                exports: Vec::with_capacity(20),
+                regular_expressions: Vec::new(),
+                facade: true,
+                has_module_syntax: false,
+            },
+            errors: Vec::new(),
         }
-        let ch = state.src[state.i];
+    }
 
-        if ch == b' ' || ch < 14 && ch > 8 {
-            continue;
+    /// Parses the source like [`parse`].
+    pub fn parse(&mut self, input: &str) -> Result<&SourceAnalysis, ParseError> {
+        self.parse_bytes(input.as_bytes())
+    }
+
+    /// Parses raw source bytes like [`parse_bytes`].
+    pub fn parse_bytes(&mut self, input: &[u8]) -> Result<&SourceAnalysis, ParseError> {
+        self.analyze(input, false)?;
+        Ok(&self.analysis)
+    }
+
+    /// Parses raw source bytes like [`Lexer::parse_bytes`], handing out the analysis instead of
+    /// lending it, while the other buffers of the lexer are kept for the next source.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn parse_bytes_owned(&mut self, input: &[u8]) -> Result<SourceAnalysis, ParseError> {
+        self.analyze(input, false)?;
+        Ok(mem::replace(&mut self.analysis, SourceAnalysis::empty()))
+    }
+
+    /// Parses the source like [`parse_with_recovery`].
+    pub fn parse_with_recovery(&mut self, input: &str) -> (&SourceAnalysis, &[ParseError]) {
+        // cannot fail when recovering
+        let _ = self.analyze(input.as_bytes(), true);
+        (&self.analysis, &self.errors)
+    }
+
+    /// Parses the source like [`parse_with_visitor`].
+    pub fn parse_with_visitor(
+        &mut self,
+        input: &str,
+        visitor: &mut dyn ModuleVisitor,
+    ) -> Result<(), ParseError> {
+        self.lex(input.as_bytes(), visitor, None, false).map(|_| ())
+    }
+
+    /// Lexes the header of the source like [`parse_header`].
+    pub fn parse_header(&mut self, input: &str) -> Result<ModuleHeader, ParseError> {
+        let mut imports = Vec::new();
+        let flags = self.lex(
+            input.as_bytes(),
+            &mut header::HeaderBuilder {
+                imports: &mut imports,
+            },
+            None,
+            true,
+        )?;
+        Ok(ModuleHeader {
+            imports,
+            may_contain_more: flags.header_ended,
+        })
+    }
+
+    /// Collects the records of the source into `self.analysis`.
+    fn analyze(&mut self, input: &[u8], recover: bool) -> Result<(), ParseError> {
+        let mut analysis = mem::replace(&mut self.analysis, SourceAnalysis::empty());
+        analysis.imports.clear();
+        analysis.exports.clear();
+        analysis.regular_expressions.clear();
+        let mut errors = mem::take(&mut self.errors);
+        errors.clear();
+
+        let result = self.lex(
+            input,
+            &mut AnalysisBuilder {
+                analysis: &mut analysis,
+            },
+            if recover { Some(&mut errors) } else { None },
+            false,
+        );
+        if let Ok(flags) = result {
+            analysis.facade = flags.facade;
+            analysis.has_module_syntax = flags.has_module_syntax;
         }
+        self.analysis = analysis;
+        self.errors = errors;
+        result.map(|_| ())
+    }
 
-        match ch as char {
-            'e' => {
-                if state.open_token_depth == 0
-                    && keyword_start(state.src, state.i)
-                    && &state.src[state.i + 1..state.i + 6] == b"xport"
-                {
-                    try_parse_export_statement(&mut state)?;
-                }
+    fn lex(
+        &mut self,
+        input: &[u8],
+        visitor: &mut dyn ModuleVisitor,
+        errors: Option<&mut Vec<ParseError>>,
+        header_only: bool,
+    ) -> Result<ModuleFlags, ParseError> {
+        self.open_token_stack.clear();
+        self.dynamic_import_stack.clear();
+
+        let mut state = ParseState {
+            src: input,
+            i: 0,
+            open_token_stack: mem::take(&mut self.open_token_stack),
+            tokens: TokenHistory::new(),
+            pending_body: None,
+            dynamic_import_stack: mem::take(&mut self.dynamic_import_stack),
+            limits: self.limits,
+            header_only,
+            statement_start: None,
+            visitor,
+            deferred: None,
+            stopped: false,
+            reached: 0,
+            imports: 0,
+            records: 0,
+            flags: ModuleFlags {
+                facade: true,
+                has_module_syntax: false,
+                header_ended: false,
+            },
+        };
+        let result = lex(&mut state, errors);
+        let flags = state.flags;
+        self.open_token_stack = state.open_token_stack;
+        self.dynamic_import_stack = state.dynamic_import_stack;
+        result.map(|()| flags)
+    }
+}
+
+impl SourceAnalysis {
+    /// An analysis without any allocations, to be replaced by the actual one.
+    fn empty() -> SourceAnalysis {
+        SourceAnalysis {
+            imports: Vec::new(),
+            exports: Vec::new(),
+            regular_expressions: Vec::new(),
+            facade: true,
+            has_module_syntax: false,
+        }
+    }
+}
+
+/// Lexes the whole source. Errors are returned as soon as they occur,
+/// unless `errors` is given, in which case they are collected there and lexing resynchronizes.
+fn lex(state: &mut ParseState, mut errors: Option<&mut Vec<ParseError>>) -> Result<(), ParseError> {
+    let len = state.src.len();
+    if len > state.limits.max_source_bytes {
+        let err = ParseError::new(
+            state.src,
+            ParseErrorKind::LimitExceeded(Limit::SourceBytes),
+            state.limits.max_source_bytes..len,
+        );
+        return match errors {
+            Some(errors) => {
+                errors.push(err);
+                Ok(())
             }
-            'i' => {
-                if keyword_start(state.src, state.i)
-                    && &state.src[state.i + 1..state.i + 6] == b"mport"
-                {
-                    try_parse_import_statement(&mut state)?;
+            None => Err(err),
+        };
+    }
+
+    // hashbang
+    if state.src.starts_with(b"#!") {
+        line_comment(state)?;
+    }
+
+    // built on the first error collected, so that positioning many of them stays linear
+    let mut line_index = None;
+    while state.i < state.src.len() && !state.stopped {
+        let header_statement = state.header_only && state.open_token_stack.is_empty();
+        if header_statement && header::ends_header(state) {
+            state.flags.header_ended = true;
+            state.stopped = true;
+            break;
+        }
+        // the only identifiers not ending the header are `import` and `export`
+        let import_or_export = header_statement && is_identifier_start(state.src[state.i]);
+        let imports = state.imports;
+        match lex_token(state) {
+            Ok(()) => state.i += 1,
+            Err(mut err) => match errors.as_mut() {
+                Some(errors) => {
+                    resync(state, &err);
+                    let src = state.src;
+                    err.locate_with(line_index.get_or_insert_with(|| LineIndex::new(src)));
+                    errors.push(err);
                 }
-            }
-            'c' => {
-                if keyword_start(state.src, state.i)
-                    && &state.src[state.i + 1..state.i + 5] == b"lass"
-                    && is_br_or_ws(state.src[state.i + 5])
-                {
-                    state.next_brace_is_class = true;
+                None => {
+                    err.locate(state.src);
+                    return Err(err);
                 }
+            },
+        }
+        if import_or_export && state.imports == imports {
+            state.flags.header_ended = true;
+            state.stopped = true;
+        }
+    }
+    if state.stopped {
+        return Ok(());
+    }
+    check_unclosed(state, errors)
+}
+
+/// Fails for a bracket still open at the end of the source.
+fn check_unclosed(
+    state: &mut ParseState,
+    errors: Option<&mut Vec<ParseError>>,
+) -> Result<(), ParseError> {
+    if let Some(&open_token) = state.open_token_stack.last() {
+        // a `${` left open also leaves its template unterminated
+        let kind = if open_token.kind == OpenTokenKind::TemplateExpression {
+            ParseErrorKind::UnterminatedTemplate
+        } else {
+            ParseErrorKind::UnbalancedBracket
+        };
+        // the start of an open token may have wrapped around when lexing a stream
+        let range = open_token.start..open_token.start.wrapping_add(1);
+        let err =
+            ParseError::new(state.src, kind, range).with_expected(closing_bracket(open_token.kind));
+        match errors {
+            Some(errors) => {
+                state.open_token_stack.clear();
+                discard_open_dynamic_imports(state);
+                errors.push(err);
             }
-            '(' => {
-                state
-                    .open_token_index_stack
-                    .resize(state.open_token_depth + 1, 0);
-                state.open_token_index_stack[state.open_token_depth] = state.last_token_index;
-                state.open_token_depth += 1;
+            None => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+/// Consumes the token starting at the current position, leaving the parsing state on its last character.
+fn lex_token(state: &mut ParseState) -> Result<(), ParseError> {
+    let class = byte_class(state.src[state.i]);
+    // identifiers and slashes are only code if they are not part of a module statement or comment
+    if !matches!(
+        class,
+        ByteClass::Whitespace
+            | ByteClass::Semicolon
+            | ByteClass::Slash
+            | ByteClass::Identifier
+            | ByteClass::Hash
+    ) {
+        state.flags.facade = false;
+    }
+    match class {
+        ByteClass::Whitespace => {}
+        ByteClass::OpenParen => {
+            push_open_token(state, OpenTokenKind::Paren, false)?;
+            push_char_token(state, TokenKind::Punctuator);
+        }
+        ByteClass::CloseParen => {
+            let open_token = match state.open_token_stack.last() {
+                Some(&open_token) if open_token.kind == OpenTokenKind::Paren => open_token,
+                open_token => return Err(unbalanced_bracket(state, open_token.copied())),
+            };
+            state.open_token_stack.pop();
+            close_open_token(state);
+            let statement_head = match open_token.preceding {
+                Some(Token {
+                    kind: TokenKind::Keyword(keyword),
+                    ..
+                }) => keyword.has_statement_head(),
+                _ => false,
+            };
+            push_char_token(state, TokenKind::CloseParen { statement_head });
+        }
+        ByteClass::OpenBrace => {
+            let (kind, expression) = brace_kind(state);
+            push_open_token(state, kind, expression)?;
+            push_char_token(state, TokenKind::Punctuator);
+        }
+        ByteClass::CloseBrace => {
+            let open_token = match state.open_token_stack.last() {
+                Some(&open_token) if open_token.kind != OpenTokenKind::Paren => open_token,
+                open_token => return Err(unbalanced_bracket(state, open_token.copied())),
+            };
+            state.open_token_stack.pop();
+            close_open_token(state);
+            if open_token.kind == OpenTokenKind::TemplateExpression {
+                template_string(state)?;
+            } else {
+                let expression = open_token.expression;
+                push_char_token(state, TokenKind::CloseBrace { expression });
             }
-            ')' => {
-                if state.open_token_depth == 0 {
-                    return Err(ParseError::from_source_and_index(input, state.i));
-                }
-                // state.open_token_index_stack.pop();
-                state.open_token_depth -= 1;
-                if let Some(di) = state.last_dynamic_import {
-                    if state.analysis.imports.len() == di + 1 {
-                        match &mut state.analysis.imports[di] {
-                            Import::Dynamic(import) => import.end = state.i,
-                            _ => panic!("Expected dynamic import"),
-                        }
-                    }
+        }
+        ByteClass::SingleQuote => {
+            let start = state.i;
+            single_quote_string(state)?;
+            push_token_from(state, TokenKind::String, start);
+        }
+        ByteClass::DoubleQuote => {
+            let start = state.i;
+            double_quote_string(state)?;
+            push_token_from(state, TokenKind::String, start);
+        }
+        ByteClass::Backtick => {
+            template_string(state)?;
+        }
+        ByteClass::Slash => match state.src.get(state.i + 1) {
+            Some(b'/') => line_comment(state)?,
+            Some(b'*') => block_comment(state)?,
+            _ => {
+                state.flags.facade = false;
+                let start = state.i;
+                if regex_allowed(state) {
+                    regular_expression(state)?;
+                    push_token_from(state, TokenKind::RegularExpression, start);
+                } else {
+                    push_token(state, TokenKind::Punctuator, start, start + 1);
                 }
             }
-            '{' => {
-                // this is a sneaky way to get around { import () {} } v { import () }
-                // block / object ambiguity without a parser (assuming source is valid)
-                if let Some(di) = state.last_dynamic_import {
-                    if state.analysis.imports.len() == di + 1 {
-                        match &state.analysis.imports[di] {
-                            Import::Dynamic(import) => {
-                                if import.end == state.last_token_index {
-                                    state.analysis.imports.pop();
-                                }
-                            }
-                            _ => panic!("Expected dynamic import"),
-                        }
-                    }
+        },
+        ByteClass::Digit => number(state),
+        ByteClass::Dot if state.src.get(state.i + 1).is_some_and(u8::is_ascii_digit) => {
+            number(state)
+        }
+        ByteClass::Identifier | ByteClass::Hash => identifier(state)?,
+        ByteClass::Dot | ByteClass::Semicolon | ByteClass::Punctuator | ByteClass::Other => {
+            punctuator(state)
+        }
+    }
+    Ok(())
+}
+
+/// Moves the parsing state past the error, to where lexing can continue.
+fn resync(state: &mut ParseState, err: &ParseError) {
+    match err.kind {
+        ParseErrorKind::UnterminatedString | ParseErrorKind::UnterminatedRegex => {
+            let kind = if err.kind == ParseErrorKind::UnterminatedString {
+                TokenKind::String
+            } else {
+                TokenKind::RegularExpression
+            };
+            push_token(state, kind, err.range.start, err.range.end);
+            state.i = next_line(state.src, err.range.end);
+        }
+        ParseErrorKind::UnbalancedBracket => {
+            let has_open_brace = state
+                .open_token_stack
+                .iter()
+                .any(|open_token| open_token.kind != OpenTokenKind::Paren);
+            if state.src.get(err.range.start) == Some(&b'}') && has_open_brace {
+                // close the parens left open inside the brace, then lex the `}` again
+                while innermost_open_token_kind(state) == Some(OpenTokenKind::Paren) {
+                    state.open_token_stack.pop();
                 }
-                state
-                    .open_class_index_stack
-                    .resize(state.open_token_depth + 1, false);
-                state.open_class_index_stack[state.open_token_depth] = state.next_brace_is_class;
-                state.next_brace_is_class = false;
-                state
-                    .open_token_index_stack
-                    .resize(state.open_token_depth + 1, 0);
-                state.open_token_index_stack[state.open_token_depth] = state.last_token_index;
-                state.open_token_depth += 1;
+                discard_open_dynamic_imports(state);
+                state.i = err.range.start;
+            } else {
+                state.i = err.range.end;
             }
-            '}' => {
-                if state.open_token_depth == 0 {
-                    return Err(ParseError::from_source_and_index(input, state.i));
-                }
-                state.open_token_depth -= 1;
-                if let Some(td) = state.template_depth {
-                    if state.open_token_depth == td {
-                        state.template_depth = state.template_stack.pop();
-                        template_string(&mut state)?;
-                    } else {
-                        if state.open_token_depth < td {
-                            return Err(ParseError::from_source_and_index(input, state.i));
-                        }
-                    }
+        }
+        ParseErrorKind::InvalidImport
+        | ParseErrorKind::InvalidExport
+        | ParseErrorKind::InvalidUtf8
+        | ParseErrorKind::UnexpectedEndOfInput => {
+            let start = state.statement_start.take().unwrap_or(err.range.start);
+            state.i = next_statement(state, start);
+        }
+        ParseErrorKind::UnterminatedTemplate | ParseErrorKind::UnterminatedBlockComment => {
+            state.i = state.src.len();
+        }
+        // limits are not recovered from
+        ParseErrorKind::LimitExceeded(_) => {
+            state.open_token_stack.clear();
+            discard_open_dynamic_imports(state);
+            state.i = state.src.len();
+        }
+    }
+}
+
+/// The index after the next line break from `i`, or the end of the source.
+fn next_line(src: &[u8], i: usize) -> usize {
+    match src[i.min(src.len())..]
+        .iter()
+        .position(|&ch| ch == b'\n' || ch == b'\r')
+    {
+        Some(offset) => i + offset + 1,
+        None => src.len(),
+    }
+}
+
+/// The index after the `;` or line break ending the statement at `i`, at which no bracket
+/// is open anymore, or the end of the source.
+///
+/// Brackets opened before `i` that are closed on the way are popped, skipping the rest of their
+/// block. Strings, templates and comments are skipped so that brackets in them are not counted.
+fn next_statement(state: &mut ParseState, mut i: usize) -> usize {
+    let src = state.src;
+    // brackets opened since `i`
+    let mut depth = 0usize;
+    while i < src.len() {
+        match src[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                if depth > 0 {
+                    depth -= 1;
+                } else if state.open_token_stack.pop().is_some() {
+                    discard_open_dynamic_imports(state);
                 }
             }
-            '\'' => {
-                single_quote_string(&mut state)?;
+            b';' | b'\n' | b'\r' if depth == 0 && state.open_token_stack.is_empty() => {
+                return i + 1;
             }
-            '"' => {
-                double_quote_string(&mut state)?;
+            quote @ (b'\'' | b'"' | b'`') => i = skip_quoted(src, i, quote),
+            // stops right before the line break, which can end the statement
+            b'/' if src.get(i + 1) == Some(&b'/') => i = next_line(src, i) - 2,
+            b'/' if src.get(i + 1) == Some(&b'*') => {
+                i = src[i + 2..]
+                    .windows(2)
+                    .position(|pair| pair == b"*/")
+                    .map_or(src.len(), |offset| i + 2 + offset + 1);
             }
-            '/' => {
-                let next_ch = state.src[state.i + 1] as char;
-                if next_ch == '/' {
-                    line_comment(&mut state)?;
-                    // dont update lastToken
-                    skip_set_last_token = true;
-                } else if next_ch == '*' {
-                    block_comment(&mut state)?;
-                    // dont update lastToken
-                    skip_set_last_token = true;
-                } else {
-                    // Division / regex ambiguity handling based on checking backtrack analysis of:
-                    // - what token came previously (lastToken)
-                    // - if a closing brace or paren, what token came before the corresponding
-                    //   opening brace or paren (lastOpenTokenIndex)
-                    let last_token = if state.last_token_index == usize::MAX {
-                        '\0'
-                    } else {
-                        state.src[state.last_token_index] as char
-                    };
-                    if last_token == '\u{0}'
-                        || is_expression_punctuator(last_token as u8)
-                            && !(last_token == '.'
-                                && (state.last_token_index > 1
-                                    && state.src[state.last_token_index - 1] >= b'0'
-                                    && state.last_token_index > 1
-                                    && state.src[state.last_token_index - 1] <= b'9'))
-                            && !(last_token == '+'
-                                && state.last_token_index > 1
-                                && state.src[state.last_token_index - 1] == b'+')
-                            && !(last_token == '-'
-                                && state.last_token_index > 1
-                                && state.src[state.last_token_index - 1] == b'-')
-                        || last_token == ')'
-                            && is_paren_keyword(
-                                state.src,
-                                state.open_token_index_stack[state.open_token_depth],
-                            )
-                        || last_token == '}'
-                            && (is_expression_terminator(
-                                state.src,
-                                state.open_token_index_stack[state.open_token_depth],
-                            ) || state.open_class_index_stack[state.open_token_depth])
-                        || is_expression_keyword(state.src, state.last_token_index)
-                        || last_token == '/' && last_slash_was_division
-                    {
-                        regular_expression(&mut state)?;
-                        last_slash_was_division = false;
-                    } else {
-                        last_slash_was_division = true;
-                    }
-                }
+            _ => {}
+        }
+        i += 1;
+    }
+    src.len()
+}
+
+/// The index of the quote closing the string or template opened at `i`, skipping escapes.
+/// Strings also end at a line break, and both at the end of the source.
+fn skip_quoted(src: &[u8], mut i: usize, quote: u8) -> usize {
+    i += 1;
+    while i < src.len() {
+        match src[i] {
+            b'\\' => i += 1,
+            b'\n' | b'\r' if quote != b'`' => return i - 1,
+            ch if ch == quote => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    src.len()
+}
+
+/// Drops the dynamic imports whose parens were never closed, when recovering from errors.
+fn discard_open_dynamic_imports(state: &mut ParseState) {
+    let depth = state.open_token_stack.len();
+    while let Some(&(import_depth, _)) = state.dynamic_import_stack.last() {
+        if import_depth < depth {
+            break;
+        }
+        state.dynamic_import_stack.pop();
+        state.imports -= 1;
+        state.records -= 1;
+    }
+}
+
+fn closing_bracket(kind: OpenTokenKind) -> &'static [&'static str] {
+    match kind {
+        OpenTokenKind::Paren => &[")"],
+        _ => &["}"],
+    }
+}
+
+/// The error for a closing bracket at the current position that does not match `open_token`.
+fn unbalanced_bracket(state: &ParseState, open_token: Option<OpenToken>) -> ParseError {
+    let err = ParseError::unlocated(ParseErrorKind::UnbalancedBracket, state.i..state.i + 1);
+    match open_token {
+        Some(OpenToken {
+            kind: OpenTokenKind::TemplateExpression,
+            start,
+            ..
+        }) => err.with_expected(&["}"]).with_secondary(
+            start.wrapping_sub(1)..start.wrapping_add(1),
+            "`${` opened here",
+        ),
+        Some(OpenToken {
+            kind: OpenTokenKind::Paren,
+            start,
+            ..
+        }) => err
+            .with_expected(&[")"])
+            .with_secondary(start..start.wrapping_add(1), "`(` opened here"),
+        Some(OpenToken { start, .. }) => err
+            .with_expected(&["}"])
+            .with_secondary(start..start.wrapping_add(1), "`{` opened here"),
+        None => err,
+    }
+}
+
+fn push_token(state: &mut ParseState, kind: TokenKind, start: usize, end: usize) {
+    state.tokens.push(Token { kind, start, end });
+}
+
+/// Pushes a token from `start` up to and including the current position.
+fn push_token_from(state: &mut ParseState, kind: TokenKind, start: usize) {
+    push_token(state, kind, start, state.i + 1);
+}
+
+/// Pushes a single character token at the current position.
+fn push_char_token(state: &mut ParseState, kind: TokenKind) {
+    push_token_from(state, kind, state.i);
+}
+
+fn token_text<'a>(state: &ParseState<'a>, token: Token) -> &'a [u8] {
+    &state.src[token.range()]
+}
+
+fn is_punctuator_token(state: &ParseState, token: Option<Token>, text: &[u8]) -> bool {
+    match token {
+        Some(token) => token.kind == TokenKind::Punctuator && token_text(state, token) == text,
+        None => false,
+    }
+}
+
+/// Opens a new nesting level for a paren, brace or template expression,
+/// remembering the token before the opening bracket for when it closes.
+fn push_open_token(
+    state: &mut ParseState,
+    kind: OpenTokenKind,
+    expression: bool,
+) -> Result<(), ParseError> {
+    if state.open_token_stack.len() >= state.limits.max_nesting_depth {
+        return Err(ParseError::unlocated(
+            ParseErrorKind::LimitExceeded(Limit::NestingDepth),
+            state.i..state.i + 1,
+        ));
+    }
+    state.open_token_stack.push(OpenToken {
+        kind,
+        start: state.i,
+        preceding: state.tokens.last(),
+        expression,
+    });
+    Ok(())
+}
+
+/// Settles the state tied to a nesting level that was just closed.
+fn close_open_token(state: &mut ParseState) {
+    let depth = state.open_token_stack.len();
+    if let Some(body) = state.pending_body {
+        if body.depth > depth {
+            state.pending_body = None;
+        }
+    }
+    if let Some(&(import_depth, _)) = state.dynamic_import_stack.last() {
+        if import_depth == depth && state.src[state.i] == b')' {
+            if let Some((_, mut import)) = state.dynamic_import_stack.pop() {
+                import.end = state.i;
+                visit(state, Record::DynamicImport(import));
             }
-            '`' => {
-                template_string(&mut state)?;
+        }
+    }
+}
+
+fn innermost_open_token_kind(state: &ParseState) -> Option<OpenTokenKind> {
+    state
+        .open_token_stack
+        .last()
+        .map(|open_token| open_token.kind)
+}
+
+/// Expects the body of a function or class at the next `{` on the current nesting depth.
+fn expect_body(state: &mut ParseState, kind: OpenTokenKind, expression: bool) {
+    state.pending_body = Some(PendingBody {
+        depth: state.open_token_stack.len(),
+        kind,
+        expression,
+    });
+}
+
+/// Tells a statement block from an object literal by the token preceding the `{`,
+/// returning the kind of brace and whether it is an expression.
+fn brace_kind(state: &mut ParseState) -> (OpenTokenKind, bool) {
+    if let Some(body) = state.pending_body {
+        if body.depth == state.open_token_stack.len() {
+            state.pending_body = None;
+            return (body.kind, body.expression);
+        }
+    }
+    let token = match state.tokens.last() {
+        Some(token) => token,
+        None => return (OpenTokenKind::Block, false),
+    };
+    let is_object = match token.kind {
+        TokenKind::Punctuator => match token_text(state, token) {
+            b"{" | b";" | b"]" | b"=>" => false,
+            // labels and case clauses only occur at statement level
+            b":" => !matches!(
+                innermost_open_token_kind(state),
+                None | Some(OpenTokenKind::Block)
+            ),
+            _ => true,
+        },
+        TokenKind::Keyword(Keyword::Do) | TokenKind::Keyword(Keyword::Else) => false,
+        // destructuring patterns
+        TokenKind::Keyword(Keyword::Var)
+        | TokenKind::Keyword(Keyword::Let)
+        | TokenKind::Keyword(Keyword::Const) => true,
+        TokenKind::Keyword(keyword) => keyword.precedes_expression(),
+        _ => false,
+    };
+    if is_object {
+        (OpenTokenKind::ObjectLiteral, true)
+    } else {
+        (OpenTokenKind::Block, false)
+    }
+}
+
+/// Division / regex ambiguity handling based on the previous token, and for a closing
+/// paren or brace, on what preceded the matching opening paren or brace.
+fn regex_allowed(state: &ParseState) -> bool {
+    let token = match state.tokens.last() {
+        Some(token) => token,
+        None => return true,
+    };
+    match token.kind {
+        TokenKind::Punctuator => match token_text(state, token) {
+            b"]" => false,
+            // a postfix increment ends an operand, a prefix one starts it
+            b"++" | b"--" => !ends_operand(state.tokens.get(1)),
+            _ => true,
+        },
+        TokenKind::CloseParen { statement_head } => statement_head,
+        TokenKind::CloseBrace { expression } => !expression,
+        TokenKind::Keyword(keyword) => keyword.precedes_expression(),
+        _ => false,
+    }
+}
+
+fn ends_operand(token: Option<Token>) -> bool {
+    match token {
+        Some(token) => match token.kind {
+            TokenKind::Identifier
+            | TokenKind::Number
+            | TokenKind::String
+            | TokenKind::Template
+            | TokenKind::RegularExpression
+            | TokenKind::CloseParen { .. } => true,
+            TokenKind::CloseBrace { expression } => expression,
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+/// Whether the next token is in expression position, as opposed to starting a statement.
+/// Decides between function or class declarations and expressions.
+fn expression_position(state: &ParseState) -> bool {
+    let token = match state.tokens.last() {
+        Some(token) => token,
+        None => return false,
+    };
+    match token.kind {
+        TokenKind::Punctuator => match token_text(state, token) {
+            b";" | b"{" | b"]" | b"++" | b"--" => false,
+            b":" => !matches!(
+                innermost_open_token_kind(state),
+                None | Some(OpenTokenKind::Block)
+            ),
+            _ => true,
+        },
+        TokenKind::Keyword(Keyword::Do)
+        | TokenKind::Keyword(Keyword::Else)
+        | TokenKind::Keyword(Keyword::Default) => false,
+        TokenKind::Keyword(keyword) => keyword.precedes_expression(),
+        _ => false,
+    }
+}
+
+/// Whether a class member or object property may start after the given token.
+fn starts_member(state: &ParseState, token: Option<Token>, class_body: bool) -> bool {
+    let token = match token {
+        Some(token) => token,
+        None => return false,
+    };
+    match token.kind {
+        // modifiers (`get`, `static`, `async`) or, in class bodies, the end of a field
+        TokenKind::Identifier => true,
+        TokenKind::Punctuator => match token_text(state, token) {
+            b"{" => true,
+            b"," => !class_body,
+            b";" => class_body,
+            _ => false,
+        },
+        TokenKind::Keyword(_) => false,
+        // the end of a field initializer
+        _ => class_body,
+    }
+}
+
+/// Whether an `import` followed by `(` is the name of a method definition
+/// (`{ import() {} }`, `class A { static import() {} }`) rather than a dynamic import.
+fn is_method_name(state: &ParseState) -> bool {
+    let class_body = match innermost_open_token_kind(state) {
+        Some(OpenTokenKind::ClassBody) => true,
+        Some(OpenTokenKind::ObjectLiteral) => false,
+        _ => return false,
+    };
+    let last_token = state.tokens.last();
+    if is_punctuator_token(state, last_token, b"*") {
+        // generator methods, `*import() {}`
+        let before = state.tokens.get(1);
+        return match before {
+            Some(token) if token.kind == TokenKind::Identifier => {
+                matches!(token_text(state, token), b"async" | b"static")
             }
-            _ => {}
+            _ => starts_member(state, before, class_body),
+        };
+    }
+    starts_member(state, last_token, class_body)
+}
+
+/// Whether the keyword starts at the current position and is not just the start of a longer identifier.
+fn keyword_at(state: &ParseState, keyword: &[u8]) -> bool {
+    state.src[state.i..].starts_with(keyword)
+        && !state
+            .src
+            .get(state.i + keyword.len())
+            .is_some_and(|&ch| is_identifier_char(ch))
+}
+
+fn is_member_access(state: &ParseState) -> bool {
+    let last_token = state.tokens.last();
+    is_punctuator_token(state, last_token, b".") || is_punctuator_token(state, last_token, b"?.")
+}
+
+/// Consumes an identifier or keyword, handling the keywords that start
+/// import and export statements or function and class bodies.
+fn identifier(state: &mut ParseState) -> Result<(), ParseError> {
+    let start = state.i;
+    while state.i + 1 < state.src.len() && is_identifier_char(state.src[state.i + 1]) {
+        state.i += 1;
+    }
+    let end = state.i + 1;
+    // property names after a dot are never keywords
+    let keyword = if is_member_access(state) {
+        None
+    } else {
+        Keyword::from_identifier(&state.src[start..end])
+    };
+    let kind = match keyword {
+        Some(Keyword::Import) => {
+            state.i = start;
+            return parse_statement(state, try_parse_import_statement);
         }
-        if skip_set_last_token {
-            skip_set_last_token = false;
-        } else {
-            state.last_token_index = state.i;
+        Some(Keyword::Export) if state.open_token_stack.is_empty() => {
+            state.i = start;
+            return parse_statement(state, try_parse_export_statement);
+        }
+        Some(Keyword::Class) => {
+            let expression = expression_position(state);
+            expect_body(state, OpenTokenKind::ClassBody, expression);
+            TokenKind::Keyword(Keyword::Class)
+        }
+        Some(Keyword::Function) => {
+            let expression = expression_position(state);
+            expect_body(state, OpenTokenKind::Block, expression);
+            TokenKind::Keyword(Keyword::Function)
         }
+        // `of` is only a keyword in the head of a for-of loop
+        Some(Keyword::Of) if !is_for_of(state) => TokenKind::Identifier,
+        Some(keyword) => TokenKind::Keyword(keyword),
+        None => TokenKind::Identifier,
+    };
+    state.flags.facade = false;
+    push_token(state, kind, start, end);
+    Ok(())
+}
+
+fn is_for_of(state: &ParseState) -> bool {
+    let in_for_head = matches!(
+        state.open_token_stack.last(),
+        Some(OpenToken {
+            kind: OpenTokenKind::Paren,
+            preceding: Some(Token {
+                kind: TokenKind::Keyword(Keyword::For),
+                ..
+            }),
+            ..
+        })
+    );
+    in_for_head
+        && match state.tokens.last() {
+            Some(token) => match token.kind {
+                TokenKind::Identifier | TokenKind::CloseBrace { .. } => true,
+                TokenKind::Punctuator => token_text(state, token) == b"]",
+                _ => false,
+            },
+            None => false,
+        }
+}
+
+fn number(state: &mut ParseState) {
+    let start = state.i;
+    while state.i + 1 < state.src.len() {
+        let ch = state.src[state.i + 1];
+        if !ch.is_ascii_alphanumeric() && ch != b'_' && ch != b'.' {
+            break;
+        }
+        state.i += 1;
     }
+    push_token_from(state, TokenKind::Number, start);
+}
 
-    if state.template_depth.is_some() || state.open_token_depth > 0 {
-        return Err(ParseError::from_source_and_index(input, state.i));
+/// Consumes a punctuator, combining the multi-character ones that matter
+/// to the lexer's decisions (`=>`, `++`, `--`, `?.`, `...`).
+fn punctuator(state: &mut ParseState) {
+    let start = state.i;
+    let ch = state.src[state.i];
+    let next = state.src.get(state.i + 1).copied();
+    match ch {
+        b'=' if next == Some(b'>') => state.i += 1,
+        b'+' | b'-' if next == Some(ch) => state.i += 1,
+        b'?' if next == Some(b'.')
+            && !state.src.get(state.i + 2).is_some_and(u8::is_ascii_digit) =>
+        {
+            state.i += 1
+        }
+        b'.' if state.src.get(state.i + 1..state.i + 3) == Some(b"..") => state.i += 2,
+        // function and class heads never contain these on their own nesting depth
+        b',' | b';' | b':' => {
+            if let Some(body) = state.pending_body {
+                if body.depth == state.open_token_stack.len() {
+                    state.pending_body = None;
+                }
+            }
+        }
+        _ => {}
     }
+    push_token_from(state, TokenKind::Punctuator, start);
+}
 
-    Ok(state.analysis)
+/// Lexes an import or export statement from the current position, keeping its start
+/// for recovering from an error in it.
+fn parse_statement(
+    state: &mut ParseState,
+    parse: fn(&mut ParseState) -> Result<(), ParseError>,
+) -> Result<(), ParseError> {
+    state.statement_start = Some(state.i);
+    parse(state)?;
+    state.statement_start = None;
+    Ok(())
 }
 
 fn try_parse_import_statement(state: &mut ParseState) -> Result<(), ParseError> {
-    state.last_dynamic_import = None;
-
     let start_index = state.i;
 
     state.i += 6;
 
-    let ch = comment_whitespace(state)? as char;
+    let ch = comment_whitespace(state)?;
     match ch {
         // dynamic import
         '(' => {
-            state
-                .open_token_index_stack
-                .resize(state.open_token_depth + 1, 0);
-            state.open_token_index_stack[state.open_token_depth] = start_index;
-            state.open_token_depth += 1;
-            if state.src[state.last_token_index] == b'.' {
-                return Ok(());
+            state.flags.facade = false;
+            let is_dynamic_import = !is_method_name(state);
+            let kind = if is_dynamic_import {
+                TokenKind::Keyword(Keyword::Import)
+            } else {
+                TokenKind::Identifier
+            };
+            push_token(state, kind, start_index, start_index + 6);
+            let depth = state.open_token_stack.len();
+            push_open_token(state, OpenTokenKind::Paren, false)?;
+            push_char_token(state, TokenKind::Punctuator);
+            if is_dynamic_import {
+                let import = DynamicImport {
+                    statement_start: start_index,
+                    start: state.i + 1,
+                    end: 0,
+                };
+                count_import(state, start_index..state.i + 1)?;
+                // reported once its closing paren is found
+                state.dynamic_import_stack.push((depth, import));
             }
-            // dynamic import indicated by positive d
-            let idx = state.analysis.imports.len();
-            state.analysis.imports.push(Import::Dynamic(DynamicImport {
-                statement_start: start_index,
-                start: state.i + 1,
-                end: 0,
-            }));
-            state.last_dynamic_import = Some(idx);
-
-            return Ok(());
+            Ok(())
         }
         // import.meta
         '.' => {
+            state.flags.facade = false;
+            push_token(
+                state,
+                TokenKind::Keyword(Keyword::Import),
+                start_index,
+                start_index + 6,
+            );
+            push_char_token(state, TokenKind::Punctuator);
             state.i += 1;
             let ch = comment_whitespace(state)?;
             if ch == 'm'
-                && &state.src[state.i + 1..state.i + 4] == b"eta"
-                && (state.last_token_index == usize::MAX
-                    || state.src[state.last_token_index] != b'.')
+                && state.src.get(state.i + 1..state.i + 4) == Some(b"eta")
+                && !state
+                    .src
+                    .get(state.i + 4)
+                    .is_some_and(|&ch| is_identifier_char(ch))
             {
-                state.analysis.imports.push(Import::Meta(ImportMeta {
+                let import = ImportMeta {
                     start: start_index,
                     end: state.i + 4,
-                }));
+                };
+                count_import(state, start_index..state.i + 4)?;
+                visit(state, Record::ImportMeta(import));
+                state.flags.has_module_syntax = true;
             }
-            return Ok(());
+            // the property name is lexed as a regular identifier
+            state.i -= 1;
+            Ok(())
         }
 
         _ => {
-            // no space after "import" -> not an import keyword
-            if ch != '"' && ch != '\'' && ch != '{' && ch != '*' && state.i == start_index + 6 {
-                return Ok(());
-            }
-
-            // import statement only permitted at base-level
-            if state.open_token_depth != 0 {
-                state.i -= 1;
+            // no space after "import" -> not an import keyword,
+            // and import statements are only permitted at base-level
+            if ch != '"' && ch != '\'' && ch != '{' && ch != '*' && state.i == start_index + 6
+                || !state.open_token_stack.is_empty()
+            {
+                state.flags.facade = false;
+                push_token(state, TokenKind::Identifier, start_index, start_index + 6);
+                state.i = start_index + 5;
                 return Ok(());
             }
+            state.flags.has_module_syntax = true;
             while state.i < state.src.len() {
                 let ch = state.src[state.i] as char;
                 if ch == '\'' || ch == '"' {
-                    read_import_string(start_index, ch, state)?;
+                    read_import_string(start_index, ch, ParseErrorKind::InvalidImport, state)?;
                     return Ok(());
                 }
                 state.i += 1;
             }
-            return Err(ParseError::from_source_and_index(state.src, state.i));
+            Err(syntax_error(
+                state,
+                ParseErrorKind::InvalidImport,
+                MODULE_SPECIFIER_QUOTES,
+            ))
         }
     }
 }
@@ -403,172 +1516,269 @@ fn try_parse_export_statement(state: &mut ParseState) -> Result<(), ParseError>
     let mut ch = comment_whitespace(state)?;
 
     if state.i == cur_pos && !is_punctuator(ch as u8) {
+        state.flags.facade = false;
+        push_token(state, TokenKind::Identifier, s_start_pos, cur_pos);
+        state.i = cur_pos - 1;
         return Ok(());
     }
 
+    push_token(
+        state,
+        TokenKind::Keyword(Keyword::Export),
+        s_start_pos,
+        cur_pos,
+    );
+    state.flags.has_module_syntax = true;
+    // only `export { ... }` and `export * ...` keep a facade, declarations are code
+    if ch != '{' && ch != '*' {
+        state.flags.facade = false;
+    }
+
     match ch {
         // export default ...
-        'd' => {
-            state.analysis.exports.push(Export {
-                start: state.i,
-                end: state.i + 7,
-            });
-            return Ok(());
+        'd' if keyword_at(state, b"default") => {
+            let local = default_declaration_name(state)?;
+            push_export(
+                state,
+                Export {
+                    start: state.i,
+                    end: state.i + 7,
+                    local,
+                },
+            )?;
+            push_token(
+                state,
+                TokenKind::Keyword(Keyword::Default),
+                state.i,
+                state.i + 7,
+            );
+            state.i += 6;
         }
 
         // export async? function*? name () {
-        'a' => {
+        'a' if keyword_at(state, b"async") => {
             state.i += 5;
             comment_whitespace(state)?;
+            if !keyword_at(state, b"function") {
+                state.i -= 1;
+                return Ok(());
+            }
             state.i += 8;
             ch = comment_whitespace(state)?;
             if ch == '*' {
                 state.i += 1;
                 comment_whitespace(state)?;
             }
-            let start_pos = state.i;
-            read_to_ws_or_punctuator(state);
-            state.analysis.exports.push(Export {
-                start: start_pos,
-                end: state.i,
-            });
-            state.i -= 1;
-            return Ok(());
+            read_declaration_name(state, OpenTokenKind::Block)?;
         }
-        'f' => {
+        'f' if keyword_at(state, b"function") => {
             state.i += 8;
             ch = comment_whitespace(state)?;
             if ch == '*' {
                 state.i += 1;
                 comment_whitespace(state)?;
             }
-            let start_pos = state.i;
-            read_to_ws_or_punctuator(state);
-            state.analysis.exports.push(Export {
-                start: start_pos,
-                end: state.i,
-            });
-            state.i -= 1;
-            return Ok(());
-        }
-
-        'c' | 'v' | 'l' => {
-            if ch == 'c' {
-                if &state.src[state.i + 1..state.i + 5] == b"lass"
-                    && is_br_or_ws_or_punctuator_not_dot(state.src[state.i + 5])
-                {
-                    state.i += 5;
-                    comment_whitespace(state)?;
-                    let start_pos = state.i;
-                    read_to_ws_or_punctuator(state);
-                    state.analysis.exports.push(Export {
-                        start: start_pos,
-                        end: state.i,
-                    });
-                    state.i -= 1;
-                    return Ok(());
-                }
-                state.i += 2;
-            }
+            read_declaration_name(state, OpenTokenKind::Block)?;
+        }
+
+        'c' if keyword_at(state, b"class") => {
+            state.i += 5;
+            comment_whitespace(state)?;
+            read_declaration_name(state, OpenTokenKind::ClassBody)?;
+        }
 
-            // export var/let/const name = ...(, name = ...)+
-            // destructured initializations not currently supported (skipped for { or [)
-            // also, lexing names after variable equals is skipped (export var p = function () { ... }, q = 5 skips "q")
-            state.i += 2;
+        // export var/let/const name = ...(, name = ...)+
+        // destructured initializations not currently supported (skipped for { or [)
+        // also, lexing names after variable equals is skipped (export var p = function () { ... }, q = 5 skips "q")
+        'c' | 'v' | 'l'
+            if keyword_at(state, b"const")
+                || keyword_at(state, b"var")
+                || keyword_at(state, b"let") =>
+        {
+            let declaration_start = state.i;
+            state.i += if ch == 'c' { 4 } else { 2 };
+            if let Some(keyword) =
+                Keyword::from_identifier(&state.src[declaration_start..state.i + 1])
+            {
+                push_token_from(state, TokenKind::Keyword(keyword), declaration_start);
+            }
             loop {
                 state.i += 1;
                 comment_whitespace(state)?;
                 let start_pos = state.i;
                 ch = read_to_ws_or_punctuator(state) as char;
                 // stops on [ { destructurings or =
-                if ch == '{' || ch == '[' || ch == '=' {
-                    state.i -= 1;
-                    return Ok(());
-                }
-                if state.i == start_pos {
-                    return Ok(());
+                if ch == '{' || ch == '[' || ch == '=' || state.i == start_pos {
+                    break;
                 }
-                state.analysis.exports.push(Export {
-                    start: start_pos,
-                    end: state.i,
-                });
+                push_export(
+                    state,
+                    Export {
+                        start: start_pos,
+                        end: state.i,
+                        local: Some(start_pos..state.i),
+                    },
+                )?;
+                push_token(state, TokenKind::Identifier, start_pos, state.i);
                 ch = comment_whitespace(state)?;
                 if ch != ',' {
                     break;
                 }
             }
             state.i -= 1;
-            return Ok(());
         }
 
         // export {...}
         '{' => {
-            state.i += 1;
-            comment_whitespace(state)?;
-            loop {
-                let start_pos = state.i;
-                read_to_ws_or_punctuator(state) as char;
-                let end_pos = state.i;
-                comment_whitespace(state)?;
-                ch = read_export_as(state, start_pos, end_pos)? as char;
-                // ,
-                if ch == ',' {
-                    state.i += 1;
-                    ch = comment_whitespace(state)?;
-                }
-                if ch == '}' {
-                    break;
-                }
-                if state.i == start_pos {
-                    return Err(ParseError::from_source_and_index(state.src, state.i));
-                }
-                if state.i > state.src.len() {
-                    return Err(ParseError::from_source_and_index(state.src, state.i));
+            // held back until it is known whether the names are local or re-exported
+            let mut exports = Vec::new();
+            let reexport = match read_export_list(state, &mut exports) {
+                Ok(reexport) => reexport,
+                Err(err) => {
+                    // the names read before the error are still reported
+                    push_exports(state, exports, false)?;
+                    return Err(err);
                 }
+            };
+            push_exports(state, exports, reexport)?;
+            if reexport {
+                state.i += 4;
+                let ch = comment_whitespace(state)?;
+                read_import_string(s_start_pos, ch, ParseErrorKind::InvalidExport, state)?;
+            } else {
+                state.i -= 1;
             }
+        }
+
+        // export *
+        '*' => {
+            push_char_token(state, TokenKind::Punctuator);
             state.i += 1;
+            comment_whitespace(state)?;
+            let mut exports = Vec::new();
+            // the char after the name is read again below
+            let _ = read_export_as(state, state.i, state.i, &mut exports)?;
+            push_exports(state, exports, true)?;
             ch = comment_whitespace(state)?;
-            if ch == 'f' && &state.src[state.i + 1..state.i + 4] == b"rom" {
+            if ch == 'f' && state.src.get(state.i + 1..state.i + 4) == Some(b"rom") {
                 state.i += 4;
-                read_import_string(s_start_pos, comment_whitespace(state)?, state)?;
+                let ch = comment_whitespace(state)?;
+                read_import_string(s_start_pos, ch, ParseErrorKind::InvalidExport, state)?;
             } else {
                 state.i -= 1;
             }
         }
 
-        // export *
-        '*' => {
+        _ => {
+            state.i -= 1;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the names of an export list from its `{` into `exports`,
+/// returning whether a `from` clause follows the list.
+fn read_export_list(state: &mut ParseState, exports: &mut Vec<Export>) -> Result<bool, ParseError> {
+    state.i += 1;
+    comment_whitespace(state)?;
+    loop {
+        let start_pos = state.i;
+        read_to_ws_or_punctuator(state);
+        let end_pos = state.i;
+        comment_whitespace(state)?;
+        let mut ch = read_export_as(state, start_pos, end_pos, exports)? as char;
+        // ,
+        if ch == ',' {
             state.i += 1;
+            ch = comment_whitespace(state)?;
+        }
+        if ch == '}' {
+            break;
+        }
+        if state.i == start_pos || state.i >= state.src.len() {
+            return Err(syntax_error(
+                state,
+                ParseErrorKind::InvalidExport,
+                &[",", "}"],
+            ));
+        }
+    }
+    push_char_token(state, TokenKind::CloseBrace { expression: false });
+    state.i += 1;
+    let ch = comment_whitespace(state)?;
+    Ok(ch == 'f' && state.src.get(state.i + 1..state.i + 4) == Some(b"rom"))
+}
+
+/// Reads the name of an exported function or class declaration, whose body follows.
+fn read_declaration_name(state: &mut ParseState, body: OpenTokenKind) -> Result<(), ParseError> {
+    let start_pos = state.i;
+    read_to_ws_or_punctuator(state);
+    push_export(
+        state,
+        Export {
+            start: start_pos,
+            end: state.i,
+            local: Some(start_pos..state.i),
+        },
+    )?;
+    push_token(state, TokenKind::Identifier, start_pos, state.i);
+    expect_body(state, body, false);
+    state.i -= 1;
+    Ok(())
+}
+
+/// Peeks at the name of a function or class declared by `export default`, without consuming it.
+fn default_declaration_name(state: &mut ParseState) -> Result<Option<Range<usize>>, ParseError> {
+    let default_pos = state.i;
+    state.i += 7;
+    comment_whitespace(state)?;
+    let mut declaration = keyword_at(state, b"class");
+    if declaration {
+        state.i += 5;
+    } else {
+        if keyword_at(state, b"async") {
+            state.i += 5;
             comment_whitespace(state)?;
-            read_export_as(state, state.i, state.i)? as char;
-            ch = comment_whitespace(state)?;
-            if ch == 'f' && &state.src[state.i + 1..state.i + 4] == b"rom" {
-                state.i += 4;
-                read_import_string(s_start_pos, comment_whitespace(state)?, state)?;
-            } else {
-                state.i -= 1;
+        }
+        declaration = keyword_at(state, b"function");
+        if declaration {
+            state.i += 8;
+            if comment_whitespace(state)? == '*' {
+                state.i += 1;
             }
         }
-
-        _ => {}
     }
-    return Ok(());
+    let mut name = None;
+    if declaration {
+        comment_whitespace(state)?;
+        let start_pos = state.i;
+        read_to_ws_or_punctuator(state);
+        // anonymous declarations, `class extends ...` is not named either
+        if state.i != start_pos && &state.src[start_pos..state.i] != b"extends" {
+            name = Some(start_pos..state.i);
+        }
+    }
+    state.i = default_pos;
+    Ok(name)
 }
 
 /// Parses an export specifier coming after the `as` keyword,
 /// and advances the parsing state to the position until after the next non-whitespace or non-comment char.
+/// The export is added to `exports`, with the name before `as` as its local name.
 fn read_export_as(
     state: &mut ParseState,
     mut start_pos: usize,
     mut end_pos: usize,
+    exports: &mut Vec<Export>,
 ) -> Result<u8, ParseError> {
     if state.i >= state.src.len() {
         return Ok(0);
     }
 
     let ch = state.src[state.i];
+    let local = start_pos..end_pos;
 
-    if ch == b'a' {
+    if keyword_at(state, b"as") {
         state.i += 2;
         comment_whitespace(state)?;
         start_pos = state.i;
@@ -578,45 +1788,95 @@ fn read_export_as(
     }
 
     if state.i != start_pos {
-        state.analysis.exports.push(Export {
+        exports.push(Export {
             start: start_pos,
             end: end_pos,
+            local: Some(local),
         });
     }
 
     Ok(ch)
 }
 
+/// Pushes the exports of an export list, dropping their local names if they are re-exported.
+fn push_exports(
+    state: &mut ParseState,
+    exports: Vec<Export>,
+    reexport: bool,
+) -> Result<(), ParseError> {
+    for mut export in exports {
+        if reexport {
+            export.local = None;
+        }
+        push_export(state, export)?;
+    }
+    Ok(())
+}
+
 fn read_import_string(
     statement_start: usize,
     ch: char,
+    kind: ParseErrorKind,
     state: &mut ParseState,
 ) -> Result<(), ParseError> {
+    let quote = state.i;
     if ch == '\'' {
-        state.i += 1;
-        let start = state.i;
         single_quote_string(state)?;
-        state.analysis.imports.push(Import::Static(StaticImport {
+        let import = StaticImport {
             statement_start,
-            start,
+            start: quote + 1,
             end: state.i,
             statement_end: state.i + 1,
-        }));
-        return Ok(());
+        };
+        push_static_import(state, import, statement_start..state.i + 1)?;
+        push_token_from(state, TokenKind::String, quote);
+        Ok(())
     } else if ch == '"' {
-        state.i += 1;
-        let start = state.i;
         double_quote_string(state)?;
-        state.analysis.imports.push(Import::Static(StaticImport {
+        let import = StaticImport {
             statement_start,
-            start,
+            start: quote + 1,
             end: state.i,
             statement_end: state.i + 1,
-        }));
-        return Ok(());
+        };
+        push_static_import(state, import, statement_start..state.i + 1)?;
+        push_token_from(state, TokenKind::String, quote);
+        Ok(())
+    } else {
+        Err(syntax_error(state, kind, MODULE_SPECIFIER_QUOTES))
+    }
+}
+
+const MODULE_SPECIFIER_QUOTES: &[&str] = &["'", "\""];
+
+/// The error for invalid syntax at the current position, or for the input ending before it.
+fn syntax_error(
+    state: &ParseState,
+    kind: ParseErrorKind,
+    expected: &'static [&'static str],
+) -> ParseError {
+    let len = state.src.len();
+    let kind = if state.i >= len {
+        ParseErrorKind::UnexpectedEndOfInput
     } else {
-        return Err(ParseError::from_source_and_index(state.src, state.i));
+        kind
+    };
+    ParseError::unlocated(kind, state.i.min(len)..(state.i + 1).min(len)).with_expected(expected)
+}
+
+/// The error for a literal or comment from `start` that the lexer ran past the end of,
+/// either at a line break or at the end of the input.
+fn unterminated(
+    state: &ParseState,
+    kind: ParseErrorKind,
+    start: usize,
+    expected: &'static [&'static str],
+) -> ParseError {
+    let mut end = (state.i + 1).min(state.src.len());
+    if end > start + 1 && matches!(state.src[end - 1], b'\n' | b'\r') {
+        end -= 1;
     }
+    ParseError::unlocated(kind, start..end).with_expected(expected)
 }
 
 /// Consumes the all the whitespace or comments until the first character
@@ -626,300 +1886,364 @@ fn comment_whitespace(state: &mut ParseState) -> Result<char, ParseError> {
     while state.i < state.src.len() {
         let ch = state.src[state.i] as char;
         if ch == '/' {
-            let next_ch = state.src[state.i + 1] as char;
-            if next_ch as char == '/' {
+            let next_ch = state.src.get(state.i + 1);
+            if next_ch == Some(&b'/') {
                 line_comment(state)?;
-            } else if next_ch == '*' {
+            } else if next_ch == Some(&b'*') {
                 block_comment(state)?;
             } else {
-                return Ok(ch);
+                break;
             }
         } else if !is_br_or_ws(ch as u8) {
-            return Ok(ch);
+            break;
         }
         state.i += 1;
     }
-    return Ok('\0');
+    state.reached = state.reached.max(state.i);
+    Ok(state.src.get(state.i).map_or('\0', |&ch| ch as char))
 }
 
+/// Consumes a template literal from its opening backtick, or its continuation from the `}`
+/// closing a substitution, up to the closing backtick or the next `${`.
 fn template_string(state: &mut ParseState) -> Result<(), ParseError> {
-    while state.i < state.src.len() {
-        match state.src[state.i] as char {
-            '$' => {
-                if state.src[state.i + 1] as char == '{' {
-                    state.i += 1;
-                    state.template_stack.push(state.template_depth.unwrap());
-                    state.open_token_depth += 1;
-                    state.template_depth = Some(state.open_token_depth);
-                    return Ok(());
-                }
+    let start = state.i;
+    let src = state.src;
+    let mut from = state.i + 1;
+    while let Some(offset) = src
+        .get(from..)
+        .and_then(|rest| memchr::memchr3(b'`', b'$', b'\\', rest))
+    {
+        let i = from + offset;
+        match src[i] {
+            b'$' if src.get(i + 1) == Some(&b'{') => {
+                state.i = i + 1;
+                push_open_token(state, OpenTokenKind::TemplateExpression, false)?;
+                push_token_from(state, TokenKind::Punctuator, state.i - 1);
+                return Ok(());
             }
-            '`' => return Ok(()),
-            '\\' => state.i += 1,
-            _ => (),
+            b'`' => {
+                state.i = i;
+                push_token_from(state, TokenKind::Template, start);
+                return Ok(());
+            }
+            b'\\' => from = i + 2,
+            _ => from = i + 1,
         }
     }
-    Err(ParseError::from_source_and_index(state.src, state.i))
+    // at the last byte, or past it after a trailing backslash
+    state.i = (from - 1).max(src.len() - 1);
+    Err(unterminated(
+        state,
+        ParseErrorKind::UnterminatedTemplate,
+        start,
+        &["`"],
+    ))
 }
 
 fn block_comment(state: &mut ParseState) -> Result<(), ParseError> {
-    state.i += 1;
-    while state.i < state.src.len() {
-        state.i += 1;
-        if state.src[state.i] as char == '*' && state.src[state.i + 1] as char == '/' {
-            state.i += 1;
-            return Ok(());
+    let start = state.i;
+    // the `*` of `/*` does not close the comment in `/*/`
+    let mut from = start + 2;
+    while let Some(offset) = memchr::memchr(b'*', &state.src[from..]) {
+        let star = from + offset;
+        match state.src.get(star + 1) {
+            Some(b'/') => {
+                state.i = star + 1;
+                return Ok(());
+            }
+            Some(_) => from = star + 1,
+            None => break,
         }
     }
-    Err(ParseError::from_source_and_index(state.src, state.i))
+    state.i = state.src.len() - 1;
+    Err(unterminated(
+        state,
+        ParseErrorKind::UnterminatedBlockComment,
+        start,
+        &["*/"],
+    ))
 }
 
 fn line_comment(state: &mut ParseState) -> Result<(), ParseError> {
-    while state.i < state.src.len() - 1 {
-        state.i += 1;
-        match state.src[state.i] as char {
-            '\n' | '\r' => return Ok(()),
-            _ => (),
-        }
+    let from = state.i + 1;
+    match memchr::memchr2(b'\n', b'\r', &state.src[from..]) {
+        Some(offset) => state.i = from + offset,
+        None => state.i = state.i.max(state.src.len() - 1),
     }
     Ok(())
 }
 
 fn single_quote_string(state: &mut ParseState) -> Result<(), ParseError> {
-    while state.i < state.src.len() - 1 {
-        state.i += 1;
-        match state.src[state.i] as char {
-            '\'' => return Ok(()),
-            '\\' => state.i += 1,
-            '\n' | '\r' => break,
-            _ => (),
-        }
-    }
-    Err(ParseError::from_source_and_index(state.src, state.i))
+    quoted_string(state, b'\'', &["'"])
 }
 
 fn double_quote_string(state: &mut ParseState) -> Result<(), ParseError> {
-    while state.i < state.src.len() - 1 {
-        state.i += 1;
-        match state.src[state.i] as char {
-            '"' => {
+    quoted_string(state, b'"', &["\""])
+}
+
+/// Consumes a string literal from its opening quote up to the closing one,
+/// searching for the quote, escapes and line breaks with `memchr`.
+fn quoted_string(
+    state: &mut ParseState,
+    quote: u8,
+    expected: &'static [&'static str],
+) -> Result<(), ParseError> {
+    let start = state.i;
+    let src = state.src;
+    let mut from = start + 1;
+    while let Some(offset) = src
+        .get(from..)
+        .and_then(|rest| memchr::memchr3(quote, b'\\', b'\n', rest))
+    {
+        let i = from + offset;
+        // a lone `\r` also ends the line, but is too rare to search for in the same pass
+        if let Some(cr) = memchr::memchr(b'\r', &src[from..i]) {
+            state.i = from + cr;
+            return Err(unterminated(
+                state,
+                ParseErrorKind::UnterminatedString,
+                start,
+                expected,
+            ));
+        }
+        match src[i] {
+            b'\\' => from = i + 2,
+            b'\n' => {
+                state.i = i;
+                return Err(unterminated(
+                    state,
+                    ParseErrorKind::UnterminatedString,
+                    start,
+                    expected,
+                ));
+            }
+            _ => {
+                state.i = i;
                 return Ok(());
             }
-            '\\' => state.i += 1,
-            '\n' | '\r' => break,
-            _ => {}
         }
     }
-    return Err(ParseError::from_source_index_and_msg(
-        state.src,
-        state.i,
-        "unterminated double quote string",
-    ));
+    let rest = src.get(from..).unwrap_or_default();
+    state.i = match memchr::memchr(b'\r', rest) {
+        Some(cr) => from + cr,
+        // at the last byte, or past it after a trailing backslash
+        None => (from - 1).max(src.len() - 1),
+    };
+    Err(unterminated(
+        state,
+        ParseErrorKind::UnterminatedString,
+        start,
+        expected,
+    ))
 }
 
-fn regex_character_class(state: &mut ParseState) -> Result<(), ParseError> {
+/// Consumes a character class, leaving the parsing state on its closing `]`.
+///
+/// Without the `v` flag classes cannot nest, so the first unescaped `]` ends the class.
+/// With `class_sets` enabled, nested classes such as `[[a-z]--[aeiou]]` are balanced instead.
+/// Returns whether an unescaped `[` was seen inside the class.
+fn regex_character_class(state: &mut ParseState, class_sets: bool) -> Result<bool, ParseError> {
+    let start = state.i;
+    let mut nested_bracket = false;
+    let mut depth = 0;
     while state.i < state.src.len() - 1 {
         state.i += 1;
         match state.src[state.i] as char {
-            ']' => return Ok(()),
+            ']' => {
+                if depth == 0 {
+                    return Ok(nested_bracket);
+                }
+                depth -= 1;
+            }
+            '[' => {
+                nested_bracket = true;
+                if class_sets {
+                    depth += 1;
+                }
+            }
             '\\' => state.i += 1,
             '\n' | '\r' => break,
             _ => (),
         }
     }
-    return Err(ParseError::from_source_and_index(state.src, state.i));
+    Err(unterminated(
+        state,
+        ParseErrorKind::UnterminatedRegex,
+        start,
+        &["]"],
+    ))
 }
 
-fn regular_expression(state: &mut ParseState) -> Result<(), ParseError> {
+/// Consumes a regular expression pattern up to its closing slash,
+/// returning whether any character class contained an unescaped `[`.
+fn regular_expression_pattern(
+    state: &mut ParseState,
+    class_sets: bool,
+) -> Result<bool, ParseError> {
+    let start = state.i;
+    let mut nested_bracket = false;
     while state.i < state.src.len() - 1 {
         state.i += 1;
         match state.src[state.i] as char {
-            '/' => return Ok(()),
-            '[' => regex_character_class(state)?,
+            '/' => return Ok(nested_bracket),
+            '[' => nested_bracket |= regex_character_class(state, class_sets)?,
             '\\' => state.i += 1,
             '\n' | '\r' => break,
             _ => (),
         }
     }
-    return Err(ParseError::from_source_index_and_msg(
-        state.src,
-        state.i,
-        "unterminated regular expression",
-    ));
+    Err(unterminated(
+        state,
+        ParseErrorKind::UnterminatedRegex,
+        start,
+        &["/"],
+    ))
 }
 
-fn read_to_ws_or_punctuator(state: &mut ParseState) -> u8 {
-    // This would probably be more "rusty", but I'm not sure about performance of it,
-    // we can test it later when we add benchmarks.
-    //
-    // state.src[i..]
-    //     .iter()
-    //     .find(|&&ch| is_br_or_ws(ch) || is_punctuator(ch))
-    //     .unwrap_or(state.src.last().expect("state.src is empty"));
-    while state.i < state.src.len() {
-        let ch = state.src[state.i];
-        if is_br_or_ws(ch) || is_punctuator(ch) {
-            return ch;
-        }
+/// Consumes the flags following the closing slash of a regular expression,
+/// leaving the parsing state on the last flag (or on the slash if there are none).
+fn regular_expression_flags(state: &mut ParseState) {
+    while state.i + 1 < state.src.len() && state.src[state.i + 1].is_ascii_alphabetic() {
         state.i += 1;
     }
-    return 0;
 }
 
-// Note: non-ascii BR and whitespace checks omitted for perf / footprint
-// if there is a significant user need this can be reconsidered
-// fn is_br(c: char) -> bool {
-//     return c == '\r' || c == '\n';
-// }
-
-fn is_br_or_ws(c: u8) -> bool {
-    return c > 8 && c < 14 || c == 32 || c == 160;
+/// Consumes a regular expression literal including its flags, and records it in the analysis.
+///
+/// The pattern is first read with flat character classes. Only if a class contains an unescaped `[`
+/// (or the flat reading fails) is it read again with the nested classes of the `v` flag,
+/// and that reading is kept when its flags do contain `v`.
+fn regular_expression(state: &mut ParseState) -> Result<(), ParseError> {
+    let start = state.i;
+    let flat = regular_expression_pattern(state, false);
+    if flat.as_ref().map_or(true, |&nested_bracket| nested_bracket) {
+        let flat_end = state.i;
+        state.i = start;
+        if regular_expression_pattern(state, true).is_ok() {
+            let flags_start = state.i + 1;
+            regular_expression_flags(state);
+            if state.src[flags_start..state.i + 1].contains(&b'v') {
+                push_regular_expression(state, start, flags_start);
+                return Ok(());
+            }
+        }
+        // the nested reading may have looked further ahead
+        state.reached = state.reached.max(state.i);
+        state.i = flat_end;
+    }
+    flat?;
+    let flags_start = state.i + 1;
+    regular_expression_flags(state);
+    push_regular_expression(state, start, flags_start);
+    Ok(())
 }
 
-fn is_br_or_ws_or_punctuator_not_dot(c: u8) -> bool {
-    return c > 8 && c < 14 || c == 32 || c == 160 || is_punctuator(c) && c != b'.';
+fn push_regular_expression(state: &mut ParseState, start: usize, flags_start: usize) {
+    let regex = RegularExpression {
+        start,
+        flags_start,
+        end: state.i + 1,
+    };
+    visit(state, Record::RegularExpression(regex));
 }
 
-fn keyword_start(src: &[u8], i: usize) -> bool {
-    return i == 0 || is_br_or_ws_or_punctuator_not_dot(src[i - 1]);
+fn push_static_import(
+    state: &mut ParseState,
+    import: StaticImport,
+    range: Range<usize>,
+) -> Result<(), ParseError> {
+    count_import(state, range)?;
+    visit(state, Record::StaticImport(import));
+    Ok(())
 }
 
-fn read_preceding_keyword(src: &[u8], i: usize, keyword_prefix: &[u8]) -> bool {
-    let length = keyword_prefix.len();
-    if i < length - 1 {
-        return false;
+fn count_import(state: &mut ParseState, range: Range<usize>) -> Result<(), ParseError> {
+    if state.imports >= state.limits.max_imports {
+        return Err(ParseError::unlocated(
+            ParseErrorKind::LimitExceeded(Limit::Imports),
+            range,
+        ));
     }
-    if &src[i - length..i] == keyword_prefix {
-        if i == length || is_br_or_ws_or_punctuator_not_dot(src[i - length - 1]) {
-            return true;
-        }
+    count_record(state, range)?;
+    state.imports += 1;
+    Ok(())
+}
+
+fn push_export(state: &mut ParseState, export: Export) -> Result<(), ParseError> {
+    count_record(state, export.export_specifier_range())?;
+    visit(state, Record::Export(export));
+    Ok(())
+}
+
+fn count_record(state: &mut ParseState, range: Range<usize>) -> Result<(), ParseError> {
+    if state.records >= state.limits.max_records {
+        return Err(ParseError::unlocated(
+            ParseErrorKind::LimitExceeded(Limit::Records),
+            range,
+        ));
     }
-    false
+    state.records += 1;
+    Ok(())
 }
 
-fn is_expression_keyword(src: &[u8], i: usize) -> bool {
-    match src[i] as char {
-        'd' => match src[i - 1] as char {
-            // void
-            'i' => read_preceding_keyword(src, i - 1, b"vo"),
-            // yield
-            'l' => read_preceding_keyword(src, i - 1, b"yie"),
-            _ => false,
-        },
-        'e' => match src[i - 1] as char {
-            's' => match src[i - 2] as char {
-                // else
-                'l' => read_preceding_keyword(src, i - 2, b"e"),
-                // case
-                'a' => read_preceding_keyword(src, i - 2, b"c"),
-                _ => false,
-            },
-            // delete
-            't' => read_preceding_keyword(src, i - 1, b"dele"),
-            _ => false,
-        },
-        'f' => {
-            if src[i - 1] as char != 'o' || src[i - 2] as char != 'e' {
-                false
-            } else {
-                match src[i - 3] as char {
-                    // instanceof
-                    'c' => read_preceding_keyword(src, i - 3, b"instan"),
-                    // typeof
-                    'p' => read_preceding_keyword(src, i - 3, b"ty"),
-                    _ => false,
-                }
+/// Passes a record to the visitor, unless it already stopped lexing.
+fn visit(state: &mut ParseState, record: Record) {
+    if state.stopped {
+        return;
+    }
+    match &mut state.deferred {
+        Some(deferred) => deferred.push(record),
+        None => {
+            if record.visit(state.visitor).is_break() {
+                state.stopped = true;
             }
         }
-        // in, return
-        'n' => read_preceding_keyword(src, i, b"i") || read_preceding_keyword(src, i, b"retur"),
-
-        // do
-        'o' => read_preceding_keyword(src, i, b"d"),
-        // debugger
-        'r' => read_preceding_keyword(src, i, b"debugge"),
-        // await
-        't' => read_preceding_keyword(src, i, b"awai"),
-        'w' => match src[i - 1] as char {
-            // new
-            'e' => read_preceding_keyword(src, i - 1, b"n"),
-            // throw
-            'o' => read_preceding_keyword(src, i - 1, b"thr"),
-            _ => false,
-        },
-        _ => false,
     }
 }
 
-fn is_paren_keyword(src: &[u8], i: usize) -> bool {
-    return match src[i] as char {
-        'e' => read_preceding_keyword(src, i, b"whil"),
-        'r' => read_preceding_keyword(src, i, b"fo"),
-        'f' => read_preceding_keyword(src, i, b"i"),
-        _ => false,
-    };
-}
-
-fn is_punctuator(ch: u8) -> bool {
-    // 23 possible punctuator endings: !%&()*+,-./:;<=>?[]^{}|~
-    return ch == b'!'
-        || ch == b'%'
-        || ch == b'&'
-        || ch > 39 && ch < 48
-        || ch > 57 && ch < 64
-        || ch == b'['
-        || ch == b']'
-        || ch == b'^'
-        || ch > 122 && ch < 127;
-}
-
-fn is_expression_punctuator(ch: u8) -> bool {
-    // 20 possible expression endings: !%&(*+,-.:;<=>?[^{|~
-    return ch == b'!'
-        || ch == b'%'
-        || ch == b'&'
-        || ch > 39 && ch < 47 && ch != 41
-        || ch > 57 && ch < 64
-        || ch == b'['
-        || ch == b'^'
-        || ch > 122 && ch < 127 && ch != b'}';
-}
-
-// detects:
-// => ; ) finally catch else class X
-// as all of these followed by a { will indicate a statement brace
-fn is_expression_terminator(src: &[u8], i: usize) -> bool {
-    match src[i] as char {
-        ';' | ')' => true,
-        'y' => read_preceding_keyword(src, i, b"finall"),
-        '>' => src[i - 1] as char == '=',
-        'h' => read_preceding_keyword(src, i, b"catc"),
-        'e' => read_preceding_keyword(src, i, b"els"),
-        _ => false,
+fn read_to_ws_or_punctuator(state: &mut ParseState) -> u8 {
+    // This would probably be more "rusty", but I'm not sure about performance of it,
+    // we can test it later when we add benchmarks.
+    //
+    // state.src[i..]
+    //     .iter()
+    //     .find(|&&ch| is_br_or_ws(ch) || is_punctuator(ch))
+    //     .unwrap_or(state.src.last().expect("state.src is empty"));
+    while state.i < state.src.len() {
+        let ch = state.src[state.i];
+        if is_br_or_ws(ch) || is_punctuator(ch) {
+            return ch;
+        }
+        state.i += 1;
     }
+    0
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn is_expression_keyword(identifier: &[u8]) -> bool {
+        Keyword::from_identifier(identifier).is_some_and(Keyword::precedes_expression)
+    }
+
     #[test]
     fn test_is_expression_keyword() {
         // debugger, delete, do, else, in, instanceof, new,
         // return, throw, typeof, void, yield ,await
-        assert!(is_expression_keyword(b"debugger", 7));
-        assert!(is_expression_keyword(b"delete", 5));
-        assert!(is_expression_keyword(b"do", 1));
-        assert!(is_expression_keyword(b"else", 3));
-        assert!(is_expression_keyword(b"in", 1));
-        assert!(is_expression_keyword(b"instanceof", 9));
-        assert!(is_expression_keyword(b"new", 2));
-        assert!(is_expression_keyword(b"return", 5));
-        assert!(is_expression_keyword(b"throw", 4));
-        assert!(is_expression_keyword(b"typeof", 5));
-        assert!(is_expression_keyword(b"void", 3));
-        assert!(is_expression_keyword(b"yield", 4));
-        assert!(is_expression_keyword(b"await", 4));
+        assert!(is_expression_keyword(b"debugger"));
+        assert!(is_expression_keyword(b"delete"));
+        assert!(is_expression_keyword(b"do"));
+        assert!(is_expression_keyword(b"else"));
+        assert!(is_expression_keyword(b"in"));
+        assert!(is_expression_keyword(b"instanceof"));
+        assert!(is_expression_keyword(b"new"));
+        assert!(is_expression_keyword(b"return"));
+        assert!(is_expression_keyword(b"throw"));
+        assert!(is_expression_keyword(b"typeof"));
+        assert!(is_expression_keyword(b"void"));
+        assert!(is_expression_keyword(b"yield"));
+        assert!(is_expression_keyword(b"await"));
     }
 
     #[test]
@@ -946,8 +2270,105 @@ export { d as a, p as b, z as c, r as d, q }"#;
     #[test]
     fn invalid_export() {
         let source = r#"export { a = };"#;
-        let err = parse(source).err().expect("Should error");
+        let err = parse(source).expect_err("Should error");
         assert_eq!(err.idx, 11);
+        assert_eq!(err.kind, ParseErrorKind::InvalidExport);
+    }
+
+    #[test]
+    fn error_kinds() {
+        let cases: &[(&str, ParseErrorKind, &str, &[&str])] = &[
+            (
+                "x = 'abc\ny",
+                ParseErrorKind::UnterminatedString,
+                "'abc",
+                &["'"],
+            ),
+            (
+                "x = \"abc",
+                ParseErrorKind::UnterminatedString,
+                "\"abc",
+                &["\""],
+            ),
+            (
+                "x = `abc",
+                ParseErrorKind::UnterminatedTemplate,
+                "`abc",
+                &["`"],
+            ),
+            (
+                "x = `${a",
+                ParseErrorKind::UnterminatedTemplate,
+                "{",
+                &["}"],
+            ),
+            (
+                "x = /abc\n/",
+                ParseErrorKind::UnterminatedRegex,
+                "/abc",
+                &["/"],
+            ),
+            (
+                "x = /[abc/",
+                ParseErrorKind::UnterminatedRegex,
+                "[abc/",
+                &["]"],
+            ),
+            (
+                "/* abc",
+                ParseErrorKind::UnterminatedBlockComment,
+                "/* abc",
+                &["*/"],
+            ),
+            ("f(a}", ParseErrorKind::UnbalancedBracket, "}", &[")"]),
+            ("a)", ParseErrorKind::UnbalancedBracket, ")", &[]),
+            ("if (a) {", ParseErrorKind::UnbalancedBracket, "{", &["}"]),
+            (
+                "import a from",
+                ParseErrorKind::UnexpectedEndOfInput,
+                "",
+                &["'", "\""],
+            ),
+            (
+                "export { a = }",
+                ParseErrorKind::InvalidExport,
+                "=",
+                &[",", "}"],
+            ),
+            (
+                "export * from a",
+                ParseErrorKind::InvalidExport,
+                "a",
+                &["'", "\""],
+            ),
+        ];
+        for &(source, kind, text, expected) in cases {
+            let err = parse(source).expect_err(source);
+            assert_eq!(err.kind, kind, "{}", source);
+            assert_eq!(&source[err.range.clone()], text, "{}", source);
+            assert_eq!(err.expected, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn scanning_boundaries() {
+        parse("'a\\'b\\\\'; \"\\\nc\"; `\\`$ ${'`'}`; /*/ */ // a").unwrap();
+        parse("a = 1 // a").unwrap();
+        parse("a = 1 /**/").unwrap();
+        let cases: &[(&str, ParseErrorKind, &str)] = &[
+            ("'a\rb'", ParseErrorKind::UnterminatedString, "'a"),
+            ("'a\\", ParseErrorKind::UnterminatedString, "'a\\"),
+            ("\"", ParseErrorKind::UnterminatedString, "\""),
+            ("`a$", ParseErrorKind::UnterminatedTemplate, "`a$"),
+            ("`a\\", ParseErrorKind::UnterminatedTemplate, "`a\\"),
+            ("/*/", ParseErrorKind::UnterminatedBlockComment, "/*/"),
+            ("/* **", ParseErrorKind::UnterminatedBlockComment, "/* **"),
+        ];
+        for &(source, kind, text) in cases {
+            let err = parse(source).expect_err(source);
+            assert_eq!(err.kind, kind, "{:?}", source);
+            assert_eq!(&source[err.range.clone()], text, "{:?}", source);
+        }
     }
 
     #[test]
@@ -972,13 +2393,25 @@ export { d as a, p as b, z as c, r as d, q }"#;
         assert_eq!(&source[exports[1].export_specifier_range()], "q");
     }
 
+    #[test]
+    fn export_with_continuation_byte_a0() {
+        // `à` is encoded as C3 A0, and A0 is not whitespace on its own
+        let source = "export const à = 1;\nexport { à as bà };";
+        let exports = parse(source).unwrap().exports;
+        assert_eq!(exports.len(), 2);
+        assert_eq!(&source[exports[0].export_specifier_range()], "à");
+        assert_eq!(&source[exports[1].export_specifier_range()], "bà");
+    }
+
     #[test]
     fn simple_import() {
         let source = r#"
           import test from "test";
           console.log(test);
         "#;
-        let SourceAnalysis { imports, exports } = parse(source).unwrap();
+        let SourceAnalysis {
+            imports, exports, ..
+        } = parse(source).unwrap();
         assert_eq!(imports.len(), 1);
         let StaticImport {
             statement_start,
@@ -1012,7 +2445,9 @@ export { d as a, p as b, z as c, r as d, q }"#;
             /* c */ d
           };
         "#;
-        let SourceAnalysis { imports, exports } = parse(source).unwrap();
+        let SourceAnalysis {
+            imports, exports, ..
+        } = parse(source).unwrap();
         assert_eq!(imports.len(), 2);
         let import1 = match &imports[0] {
             Import::Static(i) => i,
@@ -1047,6 +2482,45 @@ export { d as a, p as b, z as c, r as d, q }"#;
         assert_eq!(&source[exports[1].export_specifier_range()], "Q");
     }
 
+    #[test]
+    fn export_local_names() {
+        let source = r#"
+          export { a as b, c };
+          export var d, e = 1;
+          export async function* f () {}
+          export default class G extends H {}
+          export default function () {}
+          export default i;
+          export { j as k } from './l';
+          export * as m from './n';
+        "#;
+        let SourceAnalysis { exports, .. } = parse(source).unwrap();
+        let names: Vec<_> = exports
+            .iter()
+            .map(|export| {
+                (
+                    export.name(source.as_bytes()).unwrap(),
+                    export.local_name(source.as_bytes()).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("b", Some("a")),
+                ("c", Some("c")),
+                ("d", Some("d")),
+                ("e", Some("e")),
+                ("f", Some("f")),
+                ("default", Some("G")),
+                ("default", None),
+                ("default", None),
+                ("k", None),
+                ("m", None),
+            ]
+        );
+    }
+
     #[test]
     fn export_destructuring() {
         let source = r#"
@@ -1132,7 +2606,9 @@ export { d as a, p as b, z as c, r as d, q }"#;
         let source = r#"
           export { hello as default } from "test-dep";
         "#;
-        let SourceAnalysis { imports, exports } = parse(source).unwrap();
+        let SourceAnalysis {
+            imports, exports, ..
+        } = parse(source).unwrap();
 
         assert_eq!(imports.len(), 1);
         let import = match &imports[0] {
@@ -1274,7 +2750,9 @@ export { d as a, p as b, z as c, r as d, q }"#;
 
           import { g } from './test-circular2.js';
         "#;
-        let SourceAnalysis { imports, exports } = parse(source).unwrap();
+        let SourceAnalysis {
+            imports, exports, ..
+        } = parse(source).unwrap();
         assert_eq!(imports.len(), 1);
 
         let import = match &imports[0] {
@@ -1316,7 +2794,9 @@ export { d as a, p as b, z as c, r as d, q }"#;
             /***/
           }
         "#;
-        let SourceAnalysis { imports, exports } = parse(source).unwrap();
+        let SourceAnalysis {
+            imports, exports, ..
+        } = parse(source).unwrap();
         assert_eq!(imports.len(), 1);
 
         let import = match &imports[0] {
@@ -1335,15 +2815,17 @@ export { d as a, p as b, z as c, r as d, q }"#;
         let source = r#"
           "";
           `
-            \${
-              import(`test/\${ import(b)}`); /*
+            ${
+              import(`test/${ import(b)}`); /*
                   `  }
               */
             }
           `
           export { a }
         "#;
-        let SourceAnalysis { imports, exports } = parse(source).unwrap();
+        let SourceAnalysis {
+            imports, exports, ..
+        } = parse(source).unwrap();
         assert_eq!(imports.len(), 2);
 
         let import1 = match &imports[0] {
@@ -1361,6 +2843,29 @@ export { d as a, p as b, z as c, r as d, q }"#;
         assert_eq!(&source[exports[0].export_specifier_range()], "a");
     }
 
+    #[test]
+    fn escaped_template_substitution() {
+        // `\${` is an escaped dollar sign, so the template ends at the backtick before `test`,
+        // and the `}` after the next template is unbalanced
+        let source = r#"
+          "";
+          `
+            \${
+              import(`test/\${ import(b)}`); /*
+                  `  }
+              */
+            }
+          `
+          export { a }
+        "#;
+        let err = parse(source).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnbalancedBracket);
+        assert_eq!(&source[err.range], "}");
+
+        let source = "`\\${ import('./a.js') }`";
+        assert_eq!(parse(source).unwrap().imports.len(), 0);
+    }
+
     #[test]
     fn bracket_matching() {
         parse(
@@ -1407,12 +2912,78 @@ export { d as a, p as b, z as c, r as d, q }"#;
           }
           export { a };
         "#;
-        let SourceAnalysis { imports, exports } = parse(source).unwrap();
+        let SourceAnalysis {
+            imports, exports, ..
+        } = parse(source).unwrap();
         assert_eq!(imports.len(), 0);
         assert_eq!(exports.len(), 1);
         assert_eq!(&source[exports[0].export_specifier_range()], "a");
     }
 
+    #[test]
+    fn regex_in_template_substitution() {
+        let source = "`${/test/ + 5}`;\n`${ a / 2 }/b'`;\nexport { a };";
+        let analysis = parse(source).unwrap();
+        assert_eq!(analysis.regular_expressions.len(), 1);
+        assert_eq!(
+            &source[analysis.regular_expressions[0].expression_range()],
+            "/test/"
+        );
+        assert_eq!(analysis.exports.len(), 1);
+    }
+
+    #[test]
+    fn regular_expression_flags() {
+        let source = r#"
+          const re = /ab+c/gi;
+          x = /[/]/ / 2;
+        "#;
+        let SourceAnalysis {
+            regular_expressions,
+            ..
+        } = parse(source).unwrap();
+        assert_eq!(regular_expressions.len(), 2);
+        assert_eq!(
+            &source[regular_expressions[0].expression_range()],
+            "/ab+c/gi"
+        );
+        assert_eq!(&source[regular_expressions[0].pattern_range()], "ab+c");
+        assert_eq!(&source[regular_expressions[0].flags_range()], "gi");
+        assert_eq!(&source[regular_expressions[1].expression_range()], "/[/]/");
+        assert_eq!(&source[regular_expressions[1].flags_range()], "");
+    }
+
+    #[test]
+    fn regular_expression_class_sets() {
+        let source = r#"
+          /[[a-z]--[aeiou]]/v;
+          /[[a]/]/v;
+          import 'a';
+          /[[]/g;
+          export { b };
+        "#;
+        let SourceAnalysis {
+            imports,
+            exports,
+            regular_expressions,
+            ..
+        } = parse(source).unwrap();
+        assert_eq!(imports.len(), 1);
+        assert_eq!(exports.len(), 1);
+        assert_eq!(&source[exports[0].export_specifier_range()], "b");
+        assert_eq!(regular_expressions.len(), 3);
+        assert_eq!(
+            &source[regular_expressions[0].pattern_range()],
+            "[[a-z]--[aeiou]]"
+        );
+        assert_eq!(
+            &source[regular_expressions[1].expression_range()],
+            "/[[a]/]/v"
+        );
+        assert_eq!(&source[regular_expressions[2].pattern_range()], "[[]");
+        assert_eq!(&source[regular_expressions[2].flags_range()], "g");
+    }
+
     #[test]
     fn template_string_expression_ambiguity() {
         let source = r#"
@@ -1424,7 +2995,9 @@ export { d as a, p as b, z as c, r as d, q }"#;
           import(`$`);
           `{$}`
         "#;
-        let SourceAnalysis { imports, exports } = parse(source).unwrap();
+        let SourceAnalysis {
+            imports, exports, ..
+        } = parse(source).unwrap();
         assert_eq!(imports.len(), 2);
         assert_eq!(exports.len(), 1);
         assert_eq!(&source[exports[0].export_specifier_range()], "b");
@@ -1438,6 +3011,48 @@ export { d as a, p as b, z as c, r as d, q }"#;
         parse(source).unwrap();
     }
 
+    #[test]
+    fn facade() {
+        let analysis = parse("// re-exports\nimport './a.js';\nexport * from './b.js';\nexport { c as d } from './c.js'").unwrap();
+        assert!(analysis.facade);
+        assert!(analysis.has_module_syntax);
+
+        let analysis = parse("import a from './a.js';\nexport { a };").unwrap();
+        assert!(analysis.facade);
+
+        for source in [
+            "import a from './a.js';\nconsole.log(a);",
+            "export const a = 1;",
+            "export default './a.js';",
+            "export function f() {}",
+            "import('./a.js');",
+            "import.meta.url;",
+            "'use strict';",
+            "/a/.test(b);",
+        ]
+        .iter()
+        {
+            assert!(!parse(source).unwrap().facade, "{}", source);
+        }
+    }
+
+    #[test]
+    fn has_module_syntax() {
+        for source in ["import './a.js';", "export {};", "import.meta.url;"].iter() {
+            assert!(parse(source).unwrap().has_module_syntax, "{}", source);
+        }
+        for source in [
+            "import('./a.js');",
+            "const a = 1;",
+            "a.import(b)",
+            "x.export = 1;",
+        ]
+        .iter()
+        {
+            assert!(!parse(source).unwrap().has_module_syntax, "{}", source);
+        }
+    }
+
     #[test]
     fn empty_export() {
         let source = r#"
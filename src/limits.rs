@@ -0,0 +1,44 @@
+//! Caps on the resources spent on a single source, for lexing untrusted input.
+
+#[cfg(feature = "wasm")]
+use tsify::Tsify;
+
+/// Limits applied while parsing. Exceeding one fails with [`ParseErrorKind::LimitExceeded`].
+///
+/// The default imposes no limits.
+///
+/// [`ParseErrorKind::LimitExceeded`]: crate::ParseErrorKind::LimitExceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum number of simultaneously open parens, braces and template substitutions.
+    pub max_nesting_depth: usize,
+    /// Maximum length of the source in bytes.
+    pub max_source_bytes: usize,
+    /// Maximum number of imports, including dynamic imports and `import.meta`.
+    pub max_imports: usize,
+    /// Maximum number of imports and exports together. Regular expressions are not counted,
+    /// their number is bounded by the size of the source.
+    pub max_records: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> ParseLimits {
+        ParseLimits {
+            max_nesting_depth: usize::MAX,
+            max_source_bytes: usize::MAX,
+            max_imports: usize::MAX,
+            max_records: usize::MAX,
+        }
+    }
+}
+
+/// The limit that was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub enum Limit {
+    NestingDepth,
+    SourceBytes,
+    Imports,
+    Records,
+}
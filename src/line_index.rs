@@ -0,0 +1,289 @@
+//! Conversion between byte offsets and line / column positions.
+//!
+//! Lines are separated by the JavaScript line terminators: `\n`, `\r\n`, `\r`,
+//! U+2028 (line separator) and U+2029 (paragraph separator).
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// The unit in which columns are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnUnit {
+    Bytes,
+    /// UTF-16 code units, as used by JavaScript strings and the Language Server Protocol.
+    Utf16,
+    CodePoints,
+}
+
+/// A zero based line and column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// The start offsets of all lines of a source, built once to map many offsets to positions.
+///
+/// Sources that are not valid UTF-8 are supported: for the UTF-16 and code point units,
+/// each invalid byte counts as a single replacement character.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    src: &'a [u8],
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new<T: AsRef<[u8]> + ?Sized>(src: &'a T) -> LineIndex<'a> {
+        let src = src.as_ref();
+        let mut line_starts = vec![0];
+        let mut i = 0;
+        while i < src.len() {
+            match line_break_len(src, i) {
+                0 => i += 1,
+                len => {
+                    i += len;
+                    line_starts.push(i);
+                }
+            }
+        }
+        LineIndex { src, line_starts }
+    }
+
+    pub(crate) fn source_len(&self) -> usize {
+        self.src.len()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The byte range of the line, excluding its line terminator.
+    pub fn line_range(&self, line: usize) -> Option<Range<usize>> {
+        let start = *self.line_starts.get(line)?;
+        let end = match self.line_starts.get(line + 1) {
+            Some(&next) => next - line_break_len_before(self.src, next),
+            None => self.src.len(),
+        };
+        Some(start..end)
+    }
+
+    /// The position of the byte offset, which is clamped to the end of the source.
+    pub fn line_col(&self, offset: usize, unit: ColumnUnit) -> LineCol {
+        let offset = offset.min(self.src.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        LineCol {
+            line,
+            col: column_width(&self.src[line_start..offset], unit),
+        }
+    }
+
+    /// The byte offset of the position, or `None` if the line does not exist, the column is past
+    /// the end of the line, or the column is in the middle of a character.
+    pub fn offset(&self, position: LineCol, unit: ColumnUnit) -> Option<usize> {
+        let range = self.line_range(position.line)?;
+        if unit == ColumnUnit::Bytes {
+            let offset = range.start + position.col;
+            return if offset <= range.end {
+                Some(offset)
+            } else {
+                None
+            };
+        }
+        let mut col = 0;
+        let mut i = range.start;
+        while col < position.col && i < range.end {
+            let len = char_len(self.src, i);
+            col += if unit == ColumnUnit::Utf16 && len == 4 {
+                2
+            } else {
+                1
+            };
+            i += len;
+        }
+        if col == position.col {
+            Some(i)
+        } else {
+            None
+        }
+    }
+}
+
+/// The zero based position of the byte offset in the source, with the column in bytes.
+///
+/// Equivalent to [`LineIndex::line_col`] for a single lookup, without building an index.
+pub(crate) fn locate(src: &[u8], offset: usize) -> LineCol {
+    let offset = offset.min(src.len());
+    let mut line = 0;
+    let mut line_start = 0;
+    let mut i = 0;
+    while i < offset {
+        match line_break_len(src, i) {
+            0 => i += 1,
+            len => {
+                i += len;
+                if i <= offset {
+                    line += 1;
+                    line_start = i;
+                }
+            }
+        }
+    }
+    LineCol {
+        line,
+        col: offset - line_start,
+    }
+}
+
+/// The length of the line terminator starting at `i`, or `0` if there is none.
+fn line_break_len(src: &[u8], i: usize) -> usize {
+    match src[i] {
+        b'\n' => 1,
+        b'\r' if src.get(i + 1) == Some(&b'\n') => 2,
+        b'\r' => 1,
+        // U+2028 and U+2029
+        0xe2 if matches!(
+            src.get(i + 1..i + 3),
+            Some([0x80, 0xa8]) | Some([0x80, 0xa9])
+        ) =>
+        {
+            3
+        }
+        _ => 0,
+    }
+}
+
+/// The length of the line terminator ending right before `end`.
+fn line_break_len_before(src: &[u8], end: usize) -> usize {
+    if end >= 2 && &src[end - 2..end] == b"\r\n" {
+        2
+    } else if end >= 3 && line_break_len(src, end - 3) == 3 {
+        3
+    } else {
+        1
+    }
+}
+
+/// The length of the UTF-8 sequence starting at `i`, or `1` for an invalid byte.
+fn char_len(src: &[u8], i: usize) -> usize {
+    let len = match src[i] {
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return 1,
+    };
+    match src.get(i + 1..i + len) {
+        Some(rest) if rest.iter().all(|&b| b & 0xc0 == 0x80) => len,
+        _ => 1,
+    }
+}
+
+fn column_width(text: &[u8], unit: ColumnUnit) -> usize {
+    if unit == ColumnUnit::Bytes {
+        return text.len();
+    }
+    let mut width = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let len = char_len(text, i);
+        width += if unit == ColumnUnit::Utf16 && len == 4 {
+            2
+        } else {
+            1
+        };
+        i += len;
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_terminators() {
+        let src = "a\nb\r\nc\rd\u{2028}e\u{2029}f";
+        let index = LineIndex::new(src);
+        assert_eq!(index.line_count(), 6);
+        let lines: Vec<_> = (0..6)
+            .map(|line| &src[index.line_range(line).unwrap()])
+            .collect();
+        assert_eq!(lines, ["a", "b", "c", "d", "e", "f"]);
+        assert_eq!(index.line_range(6), None);
+        for (offset, _) in src.char_indices() {
+            assert_eq!(
+                index.line_col(offset, ColumnUnit::Bytes),
+                locate(src.as_bytes(), offset)
+            );
+        }
+    }
+
+    #[test]
+    fn column_units() {
+        let src = "x\n€😀a";
+        let index = LineIndex::new(src);
+        let offset = src.find('a').unwrap();
+        assert_eq!(
+            index.line_col(offset, ColumnUnit::Bytes),
+            LineCol { line: 1, col: 7 }
+        );
+        assert_eq!(
+            index.line_col(offset, ColumnUnit::Utf16),
+            LineCol { line: 1, col: 3 }
+        );
+        assert_eq!(
+            index.line_col(offset, ColumnUnit::CodePoints),
+            LineCol { line: 1, col: 2 }
+        );
+        for unit in [ColumnUnit::Bytes, ColumnUnit::Utf16, ColumnUnit::CodePoints].iter() {
+            let position = index.line_col(offset, *unit);
+            assert_eq!(index.offset(position, *unit), Some(offset));
+        }
+    }
+
+    #[test]
+    fn offsets_out_of_range() {
+        let src = "ab\n😀";
+        let index = LineIndex::new(src);
+        assert_eq!(
+            index.line_col(100, ColumnUnit::Bytes),
+            LineCol { line: 1, col: 4 }
+        );
+        assert_eq!(
+            index.offset(LineCol { line: 0, col: 2 }, ColumnUnit::Bytes),
+            Some(2)
+        );
+        assert_eq!(
+            index.offset(LineCol { line: 0, col: 3 }, ColumnUnit::Bytes),
+            None
+        );
+        assert_eq!(
+            index.offset(LineCol { line: 2, col: 0 }, ColumnUnit::Bytes),
+            None
+        );
+        // inside the surrogate pair
+        assert_eq!(
+            index.offset(LineCol { line: 1, col: 1 }, ColumnUnit::Utf16),
+            None
+        );
+        assert_eq!(
+            index.offset(LineCol { line: 1, col: 2 }, ColumnUnit::Utf16),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let src = b"\xff\xe2\x80a\n\xf0\x9f";
+        let index = LineIndex::new(&src[..]);
+        assert_eq!(
+            index.line_col(4, ColumnUnit::CodePoints),
+            LineCol { line: 0, col: 4 }
+        );
+        assert_eq!(
+            index.line_col(7, ColumnUnit::Utf16),
+            LineCol { line: 1, col: 2 }
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Parsing many sources at once on the work-stealing thread pool of `rayon`.
+//!
+//! The functions run on the current pool, which is the global one unless they are called
+//! inside of [`rayon::ThreadPool::install`].
+
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+use rayon::prelude::*;
+
+use crate::{Lexer, ParseError, SourceAnalysis};
+
+/// Why a file passed to [`parse_dir`] could not be analyzed.
+#[derive(Debug)]
+pub enum FileError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl From<io::Error> for FileError {
+    fn from(err: io::Error) -> FileError {
+        FileError::Io(err)
+    }
+}
+
+impl From<ParseError> for FileError {
+    fn from(err: ParseError) -> FileError {
+        FileError::Parse(err)
+    }
+}
+
+impl Display for FileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::Io(err) => Display::fmt(err, f),
+            FileError::Parse(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FileError::Io(err) => Some(err),
+            FileError::Parse(err) => Some(err),
+        }
+    }
+}
+
+/// Parses each source like [`parse`](crate::parse), spreading them over the threads of the pool,
+/// where each thread reuses the buffers of a [`Lexer`].
+///
+/// Returns the id and result of every source in the order they were given,
+/// so that one invalid source does not fail the others.
+///
+/// ```
+/// let sources = vec![("a.js", "import './b.js';"), ("c.js", "f({")];
+/// let results = es_module_lexer::parse_many(sources);
+/// assert_eq!(results[0].0, "a.js");
+/// assert_eq!(results[0].1.as_ref().unwrap().imports.len(), 1);
+/// assert!(results[1].1.is_err());
+/// ```
+pub fn parse_many<I, K, S>(sources: I) -> Vec<(K, Result<SourceAnalysis, ParseError>)>
+where
+    I: IntoIterator<Item = (K, S)>,
+    K: Send,
+    S: AsRef<str> + Send,
+{
+    sources
+        .into_iter()
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map_init(Lexer::new, |lexer, (id, source)| {
+            let result = lexer.parse_bytes_owned(source.as_ref().as_bytes());
+            (id, result)
+        })
+        .collect()
+}
+
+/// Parses every file under `root` for which `filter` returns `true`, like [`parse_bytes`]
+/// on the memory-mapped contents of the file, spreading them over the threads of the pool.
+///
+/// Returns the path and result of every file, sorted by path. Symbolic links are not followed,
+/// and a directory that cannot be read is reported with its path and the error.
+///
+/// The files are mapped into memory instead of read, so they must not be truncated
+/// or written to while they are parsed.
+///
+/// ```no_run
+/// let results = es_module_lexer::parse_dir("node_modules", |path| {
+///     path.extension().is_some_and(|extension| extension == "js" || extension == "mjs")
+/// });
+/// for (path, result) in results {
+///     match result {
+///         Ok(analysis) => println!("{}: {} imports", path.display(), analysis.imports.len()),
+///         Err(err) => println!("{}: {}", path.display(), err),
+///     }
+/// }
+/// ```
+pub fn parse_dir<P, F>(root: P, filter: F) -> Vec<(PathBuf, Result<SourceAnalysis, FileError>)>
+where
+    P: AsRef<Path>,
+    F: Fn(&Path) -> bool,
+{
+    let mut paths = Vec::new();
+    let mut walk_errors = Vec::new();
+    walk(root.as_ref(), &filter, &mut paths, &mut walk_errors);
+
+    let mut results: Vec<_> = paths
+        .into_par_iter()
+        .map_init(Lexer::new, |lexer, path| {
+            let result = parse_file(lexer, &path);
+            (path, result)
+        })
+        .collect();
+    results.extend(
+        walk_errors
+            .into_iter()
+            .map(|(path, err)| (path, Err(FileError::Io(err)))),
+    );
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+    results
+}
+
+fn walk<F>(dir: &Path, filter: &F, paths: &mut Vec<PathBuf>, errors: &mut Vec<(PathBuf, io::Error)>)
+where
+    F: Fn(&Path) -> bool,
+{
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => return errors.push((dir.to_path_buf(), err)),
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                errors.push((dir.to_path_buf(), err));
+                continue;
+            }
+        };
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => walk(&path, filter, paths, errors),
+            Ok(file_type) if file_type.is_file() && filter(&path) => paths.push(path),
+            Ok(_) => {}
+            Err(err) => errors.push((path, err)),
+        }
+    }
+}
+
+fn parse_file(lexer: &mut Lexer, path: &Path) -> Result<SourceAnalysis, FileError> {
+    let file = File::open(path)?;
+    // empty files cannot be mapped on every platform
+    if file.metadata()?.len() == 0 {
+        return Ok(lexer.parse_bytes_owned(&[])?);
+    }
+    // safe as long as the file is not modified while it is mapped, which is documented on `parse_dir`
+    let map = unsafe { Mmap::map(&file)? };
+    Ok(lexer.parse_bytes_owned(&map)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read;
+    use std::path::Path;
+
+    use super::{parse_dir, parse_many, FileError};
+    use crate::{parse, parse_bytes, SourceAnalysis};
+
+    #[test]
+    fn send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<SourceAnalysis>();
+        assert_send::<FileError>();
+    }
+
+    #[test]
+    fn input_order() {
+        let sources: Vec<(usize, String)> = (0..100)
+            .map(|i| {
+                let source = if i % 7 == 0 {
+                    format!("import './{}.js'; f(", i)
+                } else {
+                    format!("import './{}.js';", i)
+                };
+                (i, source)
+            })
+            .collect();
+        let results = parse_many(sources.iter().map(|(i, source)| (*i, source)));
+        assert_eq!(results.len(), 100);
+        for (i, (id, result)) in results.into_iter().enumerate() {
+            assert_eq!(id, i);
+            match result {
+                Ok(analysis) => {
+                    assert_ne!(i % 7, 0);
+                    assert_eq!(
+                        format!("{:?}", analysis),
+                        format!("{:?}", parse(&sources[i].1).unwrap())
+                    );
+                }
+                Err(_) => assert_eq!(i % 7, 0),
+            }
+        }
+    }
+
+    #[test]
+    fn fixtures() {
+        let results = parse_dir("fixtures", |path| {
+            path.extension().is_some_and(|extension| extension == "js")
+        });
+        assert_eq!(results.len(), 8);
+        assert!(results.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        for (path, result) in results {
+            let expected = parse_bytes(&read(&path).unwrap()).unwrap();
+            assert_eq!(
+                format!("{:?}", result.unwrap()),
+                format!("{:?}", expected),
+                "{}",
+                path.display()
+            );
+        }
+
+        let results = parse_dir("fixtures", |path| path.ends_with("rollup.min.js"));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, Path::new("fixtures/rollup.min.js"));
+    }
+
+    #[test]
+    fn missing_dir() {
+        let results = parse_dir("does-not-exist", |_| true);
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].1, Err(FileError::Io(_))));
+    }
+}
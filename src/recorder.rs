@@ -0,0 +1,66 @@
+//! The visitor of the unit tests, which records its callbacks like the one
+//! of the integration tests in `tests/common`.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+use crate::{
+    parse_with_visitor, DynamicImport, Export, ImportMeta, ModuleVisitor, ParseError,
+    RegularExpression, StaticImport,
+};
+
+/// Records the callbacks in the order they are made, as the `Debug` output of their records.
+#[derive(Default)]
+pub(crate) struct Recorder {
+    pub(crate) calls: Vec<String>,
+    /// Stops lexing once this many callbacks were made.
+    pub(crate) stop_after: Option<usize>,
+}
+
+impl Recorder {
+    fn record(&mut self, call: String) -> ControlFlow<()> {
+        self.calls.push(call);
+        if Some(self.calls.len()) == self.stop_after {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+impl ModuleVisitor for Recorder {
+    fn on_static_import(&mut self, import: StaticImport) -> ControlFlow<()> {
+        self.record(format!("{:?}", import))
+    }
+
+    fn on_dynamic_import(&mut self, import: DynamicImport) -> ControlFlow<()> {
+        self.record(format!("{:?}", import))
+    }
+
+    fn on_import_meta(&mut self, import: ImportMeta) -> ControlFlow<()> {
+        self.record(format!("{:?}", import))
+    }
+
+    fn on_export(&mut self, export: Export) -> ControlFlow<()> {
+        self.record(format!("{:?}", export))
+    }
+
+    fn on_regular_expression(&mut self, regex: RegularExpression) -> ControlFlow<()> {
+        self.record(format!("{:?}", regex))
+    }
+}
+
+/// The callbacks of [`parse_with_visitor`] on the whole source.
+pub(crate) fn record(
+    source: &str,
+    stop_after: Option<usize>,
+) -> (Vec<String>, Result<(), ParseError>) {
+    let mut recorder = Recorder {
+        stop_after,
+        ..Recorder::default()
+    };
+    let result = parse_with_visitor(source, &mut recorder);
+    (recorder.calls, result)
+}
@@ -0,0 +1,594 @@
+//! Lexing a source that arrives in chunks, keeping only a window of it in memory.
+//!
+//! The lexer needs little context besides the last few tokens: the positions of open
+//! brackets and dynamic imports are only reported, never read from the source. So the bytes
+//! before the earliest of those tokens can be dropped, with the positions kept in the state
+//! moved back by as many bytes. Those may wrap around when they point before the window,
+//! and wrap back once they are reported with the offset of the window added.
+//!
+//! A token is only lexed when enough of the source after it is buffered, and lexed again
+//! once more of it arrived when lexing it looked past the end of the window.
+
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::line_index::locate;
+use crate::token::TokenHistory;
+use crate::{
+    check_unclosed, lex_token, line_comment, DynamicImport, Limit, ModuleFlags, ModuleVisitor,
+    OpenToken, ParseError, ParseErrorKind, ParseLimits, ParseState, PendingBody, Record,
+};
+
+/// The number of bytes buffered after a token before it is lexed, unless the stream ended.
+/// Tokens are lexed again when they turn out to be longer.
+const LOOKAHEAD: usize = 64 * 1024;
+
+/// How close to the end of the window lexing a token may look without being lexed again,
+/// which covers peeking at the few bytes after a token, like for `import.meta`.
+const MARGIN: usize = 16;
+
+/// Lexes a source pushed in chunks, reporting its records to a visitor as they are found,
+/// with their offsets in the whole source.
+///
+/// Only the part of the source after the last few tokens is kept in memory, so the memory
+/// used is bounded by the longest token plus a constant, rather than by the size of the source.
+/// Records are reported in the same order as by [`parse_with_visitor`].
+///
+/// The lines and columns of errors are counted like for the whole source, except that they
+/// are `0` for an error starting before the part of the source that is still kept, like an
+/// unclosed bracket opened long before the end.
+///
+/// ```
+/// use std::ops::ControlFlow;
+/// use es_module_lexer::{ModuleVisitor, StaticImport, StreamLexer};
+///
+/// struct Specifiers(Vec<std::ops::Range<usize>>);
+///
+/// impl ModuleVisitor for Specifiers {
+///     fn on_static_import(&mut self, import: StaticImport) -> ControlFlow<()> {
+///         self.0.push(import.module_specifier_range());
+///         ControlFlow::Continue(())
+///     }
+/// }
+///
+/// let mut visitor = Specifiers(Vec::new());
+/// let mut lexer = StreamLexer::new();
+/// for chunk in ["import a from './", "a.js';\nimport './b.js';"].iter() {
+///     lexer.push(chunk.as_bytes(), &mut visitor).unwrap();
+/// }
+/// lexer.finish(&mut visitor).unwrap();
+/// assert_eq!(visitor.0, vec![15..21, 32..38]);
+/// ```
+///
+/// [`parse_with_visitor`]: crate::parse_with_visitor
+#[derive(Debug)]
+pub struct StreamLexer {
+    limits: ParseLimits,
+    /// The part of the source that is kept.
+    buffer: Vec<u8>,
+    /// The offset of the start of `buffer` in the source.
+    base: usize,
+    /// The number of line breaks before `base`, and the offset of the line `base` is on.
+    lines: usize,
+    line_start: usize,
+    /// The length of `buffer` needed to lex the next token.
+    wait_until: usize,
+    /// Whether lexing stopped, because of an error or the visitor.
+    stopped: bool,
+    lookahead: usize,
+    // the lexing state between chunks, relative to `base`
+    i: usize,
+    reached: usize,
+    open_token_stack: Vec<OpenToken>,
+    tokens: TokenHistory,
+    pending_body: Option<PendingBody>,
+    dynamic_import_stack: Vec<(usize, DynamicImport)>,
+    imports: usize,
+    records: usize,
+    flags: ModuleFlags,
+}
+
+impl Default for StreamLexer {
+    fn default() -> StreamLexer {
+        StreamLexer::new()
+    }
+}
+
+impl StreamLexer {
+    pub fn new() -> StreamLexer {
+        StreamLexer::with_limits(ParseLimits::default())
+    }
+
+    /// A lexer failing once any of the given limits is exceeded,
+    /// where the size of the source is that of all chunks together.
+    pub fn with_limits(limits: ParseLimits) -> StreamLexer {
+        StreamLexer {
+            limits,
+            buffer: Vec::new(),
+            base: 0,
+            lines: 0,
+            line_start: 0,
+            wait_until: 0,
+            stopped: false,
+            lookahead: LOOKAHEAD,
+            i: 0,
+            reached: 0,
+            open_token_stack: Vec::with_capacity(50),
+            tokens: TokenHistory::new(),
+            pending_body: None,
+            dynamic_import_stack: Vec::with_capacity(10),
+            imports: 0,
+            records: 0,
+            flags: ModuleFlags {
+                facade: true,
+                has_module_syntax: false,
+                header_ended: false,
+            },
+        }
+    }
+
+    /// Appends the next chunk of the source, and lexes as much of it as possible.
+    ///
+    /// Once lexing failed or the visitor stopped it, further chunks are ignored.
+    pub fn push(
+        &mut self,
+        chunk: &[u8],
+        visitor: &mut dyn ModuleVisitor,
+    ) -> Result<(), ParseError> {
+        if self.stopped {
+            return Ok(());
+        }
+        let len = self.base + self.buffer.len() + chunk.len();
+        if len > self.limits.max_source_bytes {
+            self.stopped = true;
+            return Err(ParseError::new(
+                b"",
+                ParseErrorKind::LimitExceeded(Limit::SourceBytes),
+                self.limits.max_source_bytes..len,
+            ));
+        }
+        self.buffer.extend_from_slice(chunk);
+        self.lex(visitor, false)?;
+        self.compact();
+        Ok(())
+    }
+
+    /// Lexes the rest of the source after its last chunk was pushed.
+    pub fn finish(mut self, visitor: &mut dyn ModuleVisitor) -> Result<(), ParseError> {
+        if self.stopped {
+            return Ok(());
+        }
+        self.lex(visitor, true)
+    }
+
+    /// Lexes the tokens of the buffer that can be, or all of them for the last chunk.
+    fn lex(&mut self, visitor: &mut dyn ModuleVisitor, last: bool) -> Result<(), ParseError> {
+        let mut deferred = Vec::new();
+        let mut state = ParseState {
+            src: &self.buffer,
+            i: self.i,
+            open_token_stack: mem::take(&mut self.open_token_stack),
+            tokens: self.tokens,
+            pending_body: self.pending_body,
+            dynamic_import_stack: mem::take(&mut self.dynamic_import_stack),
+            limits: self.limits,
+            header_only: false,
+            statement_start: None,
+            visitor,
+            deferred: Some(&mut deferred),
+            stopped: false,
+            reached: self.reached,
+            imports: self.imports,
+            records: self.records,
+            flags: self.flags,
+        };
+        let mut result = lex_window(
+            &mut state,
+            self.base,
+            self.lookahead,
+            &mut self.wait_until,
+            last,
+        );
+        if result.is_ok() && last && !state.stopped {
+            result = check_unclosed(&mut state, None);
+        }
+        self.stopped = state.stopped || result.is_err();
+        self.i = state.i;
+        self.reached = state.reached;
+        self.open_token_stack = state.open_token_stack;
+        self.tokens = state.tokens;
+        self.pending_body = state.pending_body;
+        self.dynamic_import_stack = state.dynamic_import_stack;
+        self.imports = state.imports;
+        self.records = state.records;
+        self.flags = state.flags;
+        result.map_err(|err| self.absolute_error(err))
+    }
+
+    /// Drops the start of the buffer that is no longer needed, once it is
+    /// at least half of the buffer, so that every byte is moved a constant number of times.
+    fn compact(&mut self) {
+        let mut cut = self
+            .tokens
+            .start()
+            .map_or(self.i, |start| start.min(self.i))
+            .saturating_sub(MARGIN);
+        // keep line terminators of more than one byte whole, to count them once
+        while cut > 0 && matches!(self.buffer[cut - 1], b'\r' | 0xe2 | 0x80) {
+            cut -= 1;
+        }
+        if cut < self.buffer.len() / 2 || cut == 0 {
+            return;
+        }
+
+        let position = locate(&self.buffer, cut);
+        if position.line > 0 {
+            self.line_start = self.base + cut - position.col;
+        }
+        self.lines += position.line;
+        self.buffer.drain(..cut);
+        self.base += cut;
+
+        self.i -= cut;
+        self.reached = self.reached.saturating_sub(cut);
+        self.wait_until = self.wait_until.saturating_sub(cut);
+        self.tokens.rebase(cut);
+        for open_token in &mut self.open_token_stack {
+            open_token.start = open_token.start.wrapping_sub(cut);
+            if let Some(token) = &mut open_token.preceding {
+                token.start = token.start.wrapping_sub(cut);
+                token.end = token.end.wrapping_sub(cut);
+            }
+        }
+        for (_, import) in &mut self.dynamic_import_stack {
+            import.statement_start = import.statement_start.wrapping_sub(cut);
+            import.start = import.start.wrapping_sub(cut);
+            import.end = import.end.wrapping_sub(cut);
+        }
+    }
+
+    /// Moves an error in the buffer to its position in the whole source.
+    fn absolute_error(&self, mut err: ParseError) -> ParseError {
+        err.locate(&self.buffer);
+        // the line and column are already `0` for an error before the buffer
+        if err.line == 1 {
+            err.col += self.base - self.line_start;
+        }
+        if err.line > 0 {
+            err.line += self.lines;
+        }
+        err.idx = err.idx.wrapping_add(self.base);
+        err.range = err.range.start.wrapping_add(self.base)..err.range.end.wrapping_add(self.base);
+        if let Some(label) = &mut err.secondary {
+            label.range =
+                label.range.start.wrapping_add(self.base)..label.range.end.wrapping_add(self.base);
+        }
+        err
+    }
+}
+
+/// Lexes the tokens of the window that are followed by at least `lookahead` bytes,
+/// or all of them for the last chunk, reporting the records of each token once it is complete.
+fn lex_window(
+    state: &mut ParseState,
+    base: usize,
+    lookahead: usize,
+    wait_until: &mut usize,
+    last: bool,
+) -> Result<(), ParseError> {
+    let len = state.src.len();
+    while state.i < len && !state.stopped {
+        if !last && len < (state.i + lookahead).max(*wait_until) {
+            break;
+        }
+        let checkpoint = Checkpoint::new(state);
+        let result = if base == 0 && state.i == 0 && state.src.starts_with(b"#!") {
+            line_comment(state)
+        } else {
+            lex_token(state)
+        };
+        if !last && state.i.max(state.reached) + MARGIN >= len {
+            // the token may continue after the window, so wait for twice as much of it
+            *wait_until = checkpoint.i + 2 * (len - checkpoint.i);
+            checkpoint.restore(state);
+            break;
+        }
+        // records found before an error are reported like when lexing the whole source
+        forward_records(state, base);
+        result?;
+        state.i += 1;
+    }
+    Ok(())
+}
+
+/// Reports the records of the last token to the visitor.
+fn forward_records(state: &mut ParseState, base: usize) {
+    let deferred = match &mut state.deferred {
+        Some(deferred) => deferred,
+        None => return,
+    };
+    for record in deferred.drain(..) {
+        if !state.stopped && offset_record(record, base).visit(state.visitor).is_break() {
+            state.stopped = true;
+        }
+    }
+}
+
+fn offset_record(record: Record, base: usize) -> Record {
+    match record {
+        Record::StaticImport(mut import) => {
+            import.statement_start = import.statement_start.wrapping_add(base);
+            import.start += base;
+            import.end += base;
+            import.statement_end += base;
+            Record::StaticImport(import)
+        }
+        Record::DynamicImport(mut import) => {
+            import.statement_start = import.statement_start.wrapping_add(base);
+            import.start = import.start.wrapping_add(base);
+            import.end += base;
+            Record::DynamicImport(import)
+        }
+        Record::ImportMeta(mut import) => {
+            import.start += base;
+            import.end += base;
+            Record::ImportMeta(import)
+        }
+        Record::Export(mut export) => {
+            export.start += base;
+            export.end += base;
+            if let Some(local) = &mut export.local {
+                *local = local.start + base..local.end + base;
+            }
+            Record::Export(export)
+        }
+        Record::RegularExpression(mut regex) => {
+            regex.start += base;
+            regex.flags_start += base;
+            regex.end += base;
+            Record::RegularExpression(regex)
+        }
+    }
+}
+
+/// The state before a token, to lex it again once more of the source arrived.
+///
+/// A token closes at most one bracket and one dynamic import, so only the top of those
+/// stacks is saved.
+struct Checkpoint {
+    i: usize,
+    reached: usize,
+    tokens: TokenHistory,
+    pending_body: Option<PendingBody>,
+    flags: ModuleFlags,
+    imports: usize,
+    records: usize,
+    open_tokens: (usize, Option<OpenToken>),
+    dynamic_imports: (usize, Option<(usize, DynamicImport)>),
+}
+
+impl Checkpoint {
+    fn new(state: &ParseState) -> Checkpoint {
+        Checkpoint {
+            i: state.i,
+            reached: state.reached,
+            tokens: state.tokens,
+            pending_body: state.pending_body,
+            flags: state.flags,
+            imports: state.imports,
+            records: state.records,
+            open_tokens: (
+                state.open_token_stack.len(),
+                state.open_token_stack.last().copied(),
+            ),
+            dynamic_imports: (
+                state.dynamic_import_stack.len(),
+                state.dynamic_import_stack.last().cloned(),
+            ),
+        }
+    }
+
+    fn restore(self, state: &mut ParseState) {
+        state.i = self.i;
+        state.reached = self.reached;
+        state.tokens = self.tokens;
+        state.pending_body = self.pending_body;
+        state.flags = self.flags;
+        state.imports = self.imports;
+        state.records = self.records;
+        restore_stack(&mut state.open_token_stack, self.open_tokens);
+        restore_stack(&mut state.dynamic_import_stack, self.dynamic_imports);
+        if let Some(deferred) = &mut state.deferred {
+            deferred.clear();
+        }
+    }
+}
+
+fn restore_stack<T>(stack: &mut Vec<T>, (len, top): (usize, Option<T>)) {
+    debug_assert!(stack.len() + 1 >= len);
+    if let Some(top) = top {
+        stack.truncate(len - 1);
+        stack.push(top);
+    } else {
+        stack.clear();
+    }
+}
+
+/// Lexes the source read from `reader` in chunks with a [`StreamLexer`], reporting
+/// its records to the visitor.
+///
+/// Stops reading once the visitor stops lexing. Parse errors are returned as errors of kind
+/// [`InvalidData`](std::io::ErrorKind::InvalidData) wrapping the [`ParseError`].
+///
+/// ```no_run
+/// # struct Visitor;
+/// # impl es_module_lexer::ModuleVisitor for Visitor {}
+/// let file = std::fs::File::open("bundle.js")?;
+/// es_module_lexer::parse_reader(file, &mut Visitor)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_reader<R: std::io::Read>(
+    mut reader: R,
+    visitor: &mut dyn ModuleVisitor,
+) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+
+    let mut lexer = StreamLexer::new();
+    let mut chunk = vec![0; LOOKAHEAD];
+    while !lexer.stopped {
+        let len = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        lexer
+            .push(&chunk[..len], visitor)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+    }
+    lexer
+        .finish(visitor)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::StreamLexer;
+    use crate::recorder::{record, Recorder};
+    use crate::ParseError;
+
+    fn lex(source: &[u8], stop_after: Option<usize>) -> (Vec<String>, Result<(), ParseError>) {
+        record(core::str::from_utf8(source).unwrap(), stop_after)
+    }
+
+    fn lex_stream(
+        source: &[u8],
+        chunk_len: usize,
+        lookahead: usize,
+        stop_after: Option<usize>,
+    ) -> (Vec<String>, Result<(), ParseError>) {
+        let mut recorder = Recorder {
+            stop_after,
+            ..Recorder::default()
+        };
+        let mut lexer = StreamLexer::new();
+        lexer.lookahead = lookahead;
+        let mut result = Ok(());
+        for chunk in source.chunks(chunk_len) {
+            result = lexer.push(chunk, &mut recorder);
+            if result.is_err() {
+                break;
+            }
+        }
+        if result.is_ok() {
+            result = lexer.finish(&mut recorder);
+        }
+        (recorder.calls, result)
+    }
+
+    /// Random sources built from pieces that open, close and end tokens.
+    fn sources() -> impl Iterator<Item = Vec<u8>> {
+        const PIECES: &[&str] = &[
+            "import a from './a.js';",
+            "import('./b.js')",
+            "import(",
+            "import.meta.url",
+            "export { c as d };",
+            "export default /e/g;",
+            "`f${",
+            "`",
+            "}",
+            "{",
+            "(",
+            ")",
+            "'g'",
+            "\"",
+            "/",
+            "/* h */",
+            "// i\n",
+            "\n",
+            "\r\n",
+            "\u{2028}",
+            " ",
+            "j",
+            "return ",
+            "1 / 2",
+        ];
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        (0..500).map(move |_| {
+            let mut source = String::new();
+            for _ in 0..seed % 40 {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                source.push_str(PIECES[(seed >> 32) as usize % PIECES.len()]);
+            }
+            source.into_bytes()
+        })
+    }
+
+    fn assert_same(
+        expected: &(Vec<String>, Result<(), ParseError>),
+        actual: &(Vec<String>, Result<(), ParseError>),
+        source: &[u8],
+    ) {
+        let source = String::from_utf8_lossy(source);
+        assert_eq!(expected.0, actual.0, "{:?}", source);
+        match (&expected.1, &actual.1) {
+            (Ok(()), Ok(())) => {}
+            (Err(expected), Err(actual)) => {
+                assert_eq!(expected.kind, actual.kind, "{:?}", source);
+                assert_eq!(expected.range, actual.range, "{:?}", source);
+                assert_eq!(expected.secondary, actual.secondary, "{:?}", source);
+                // positions before the window are not known
+                if actual.line != 0 {
+                    assert_eq!(
+                        (expected.line, expected.col),
+                        (actual.line, actual.col),
+                        "{:?}",
+                        source
+                    );
+                }
+            }
+            (expected, actual) => panic!("{:?}: {:?} != {:?}", source, expected, actual),
+        }
+    }
+
+    #[test]
+    fn matches_whole_source() {
+        for source in sources() {
+            let expected = lex(&source, None);
+            for &(chunk_len, lookahead) in &[(1, 1), (3, 0), (7, 2), (64, 1), (1024, 1024)] {
+                let actual = lex_stream(&source, chunk_len, lookahead, None);
+                assert_same(&expected, &actual, &source);
+            }
+        }
+    }
+
+    #[test]
+    fn stop_early() {
+        for source in sources() {
+            let expected = lex(&source, Some(2));
+            assert_same(&expected, &lex_stream(&source, 5, 1, Some(2)), &source);
+        }
+    }
+
+    #[test]
+    fn compacts_buffer() {
+        let source = "import './a.js';\n".repeat(1000);
+        let mut recorder = Recorder::default();
+        let mut lexer = StreamLexer::new();
+        lexer.lookahead = 64;
+        for chunk in source.as_bytes().chunks(100) {
+            lexer.push(chunk, &mut recorder).unwrap();
+            assert!(lexer.buffer.len() < 512, "{}", lexer.buffer.len());
+        }
+        assert!(lexer.lines > 900);
+        lexer.finish(&mut recorder).unwrap();
+        assert_eq!(recorder.calls.len(), 1000);
+    }
+}
@@ -0,0 +1,217 @@
+//! A small typed history of the most recent tokens, from which the lexer decides
+//! regex vs. division, block vs. object literal and keyword vs. property name.
+
+use core::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Keyword {
+    Await,
+    Case,
+    Class,
+    Const,
+    Debugger,
+    Default,
+    Delete,
+    Do,
+    Else,
+    Export,
+    Extends,
+    For,
+    Function,
+    If,
+    Import,
+    In,
+    Instanceof,
+    Let,
+    New,
+    Of,
+    Return,
+    Throw,
+    Typeof,
+    Var,
+    Void,
+    While,
+    With,
+    Yield,
+}
+
+impl Keyword {
+    pub(crate) fn from_identifier(identifier: &[u8]) -> Option<Keyword> {
+        Some(match identifier {
+            b"await" => Keyword::Await,
+            b"case" => Keyword::Case,
+            b"class" => Keyword::Class,
+            b"const" => Keyword::Const,
+            b"debugger" => Keyword::Debugger,
+            b"default" => Keyword::Default,
+            b"delete" => Keyword::Delete,
+            b"do" => Keyword::Do,
+            b"else" => Keyword::Else,
+            b"export" => Keyword::Export,
+            b"extends" => Keyword::Extends,
+            b"for" => Keyword::For,
+            b"function" => Keyword::Function,
+            b"if" => Keyword::If,
+            b"import" => Keyword::Import,
+            b"in" => Keyword::In,
+            b"instanceof" => Keyword::Instanceof,
+            b"let" => Keyword::Let,
+            b"new" => Keyword::New,
+            b"of" => Keyword::Of,
+            b"return" => Keyword::Return,
+            b"throw" => Keyword::Throw,
+            b"typeof" => Keyword::Typeof,
+            b"var" => Keyword::Var,
+            b"void" => Keyword::Void,
+            b"while" => Keyword::While,
+            b"with" => Keyword::With,
+            b"yield" => Keyword::Yield,
+            _ => return None,
+        })
+    }
+
+    /// Whether an expression may follow the keyword, so that a `/` after it starts a regex.
+    pub(crate) fn precedes_expression(self) -> bool {
+        matches!(
+            self,
+            Keyword::Await
+                | Keyword::Case
+                | Keyword::Debugger
+                | Keyword::Default
+                | Keyword::Delete
+                | Keyword::Do
+                | Keyword::Else
+                | Keyword::Extends
+                | Keyword::In
+                | Keyword::Instanceof
+                | Keyword::New
+                | Keyword::Of
+                | Keyword::Return
+                | Keyword::Throw
+                | Keyword::Typeof
+                | Keyword::Void
+                | Keyword::Yield
+        )
+    }
+
+    /// Whether the keyword is followed by a parenthesized head after which a statement starts,
+    /// as in `if (a) /re/.test(b)`.
+    pub(crate) fn has_statement_head(self) -> bool {
+        matches!(
+            self,
+            Keyword::If | Keyword::For | Keyword::While | Keyword::With
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Identifier,
+    Keyword(Keyword),
+    Number,
+    String,
+    Template,
+    RegularExpression,
+    Punctuator,
+    /// A `)`, noting whether it closed the head of an `if`, `for`, `while` or `with` statement.
+    CloseParen {
+        statement_head: bool,
+    },
+    /// A `}`, noting whether it closed an expression
+    /// (an object literal, or a function or class expression) rather than a statement.
+    CloseBrace {
+        expression: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    pub(crate) fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+const HISTORY_SIZE: usize = 4;
+
+/// Ring buffer holding the last few tokens.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TokenHistory {
+    tokens: [Option<Token>; HISTORY_SIZE],
+    next: usize,
+}
+
+impl TokenHistory {
+    pub(crate) fn new() -> TokenHistory {
+        TokenHistory {
+            tokens: [None; HISTORY_SIZE],
+            next: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, token: Token) {
+        self.tokens[self.next] = Some(token);
+        self.next = (self.next + 1) % HISTORY_SIZE;
+    }
+
+    /// The `n`-th most recent token, `0` being the last one.
+    pub(crate) fn get(&self, n: usize) -> Option<Token> {
+        debug_assert!(n < HISTORY_SIZE);
+        self.tokens[(self.next + HISTORY_SIZE - 1 - n) % HISTORY_SIZE]
+    }
+
+    pub(crate) fn last(&self) -> Option<Token> {
+        self.get(0)
+    }
+
+    /// The start of the earliest token in the history.
+    pub(crate) fn start(&self) -> Option<usize> {
+        self.tokens.iter().flatten().map(|token| token.start).min()
+    }
+
+    /// Moves the tokens `offset` bytes back, after that many bytes before them were dropped.
+    pub(crate) fn rebase(&mut self, offset: usize) {
+        for token in self.tokens.iter_mut().flatten() {
+            token.start -= offset;
+            token.end -= offset;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(start: usize) -> Token {
+        Token {
+            kind: TokenKind::Identifier,
+            start,
+            end: start + 1,
+        }
+    }
+
+    #[test]
+    fn history_wraps_around() {
+        let mut history = TokenHistory::new();
+        assert!(history.last().is_none());
+        for start in 0..6 {
+            history.push(token(start));
+        }
+        assert_eq!(history.get(0).unwrap().start, 5);
+        assert_eq!(history.get(1).unwrap().start, 4);
+        assert_eq!(history.get(3).unwrap().start, 2);
+    }
+
+    #[test]
+    fn history_before_first_tokens() {
+        let mut history = TokenHistory::new();
+        history.push(token(0));
+        assert_eq!(history.last().unwrap().start, 0);
+        assert!(history.get(1).is_none());
+    }
+}
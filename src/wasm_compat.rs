@@ -0,0 +1,346 @@
+//! The analysis as plain JavaScript objects, in the shape returned by the `es-module-lexer` package,
+//! so that code written against it can use this crate without changes.
+#![cfg(feature = "wasm")]
+
+use js_sys::{Array, Object, Reflect};
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::wasm_types::{utf16_idx, utf16_line_col, Utf16Offsets};
+use crate::{parse_with_limits, Import, ParseError, ParseLimits, SourceAnalysis};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT_TYPES: &'static str = r#"
+/** The error thrown by `parseCompat`, with the offset of the error in UTF-16 code units. */
+export interface CompatParseError extends Error {
+  idx: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Array<CompatResult | CompatParseError>")]
+    pub type CompatResults;
+}
+
+/// An import in the shape of `es-module-lexer`, with offsets in UTF-16 code units.
+#[derive(Serialize, Tsify)]
+pub struct CompatImport {
+    /// The module specifier with escapes decoded, `undefined` for `import.meta`
+    /// and non-literal dynamic imports.
+    n: Option<String>,
+    /// 1 for static imports, 2 for dynamic imports and 3 for `import.meta`.
+    #[tsify(type = "1 | 2 | 3")]
+    t: u32,
+    /// The start of the module specifier, or of the expression of a dynamic import.
+    s: usize,
+    e: usize,
+    /// The start of the statement or expression.
+    ss: usize,
+    se: usize,
+    /// -1 for static imports, -2 for `import.meta` and the offset of the paren for dynamic imports.
+    d: isize,
+    /// Always -1, import attributes are not parsed.
+    a: isize,
+}
+
+/// An export in the shape of `es-module-lexer`, with offsets in UTF-16 code units.
+#[derive(Serialize, Tsify)]
+pub struct CompatExport {
+    n: String,
+    s: usize,
+    e: usize,
+    /// The local name, `undefined` for re-exports and for `export default` of an expression.
+    ln: Option<String>,
+    /// -1 without a local name.
+    ls: isize,
+    le: isize,
+}
+
+/// `[imports, exports, facade, hasModuleSyntax]`.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct CompatResult(Vec<CompatImport>, Vec<CompatExport>, bool, bool);
+
+/// Values of the `t` field of an import.
+pub(crate) const IMPORT_TYPE_STATIC: u32 = 1;
+pub(crate) const IMPORT_TYPE_DYNAMIC: u32 = 2;
+pub(crate) const IMPORT_TYPE_IMPORT_META: u32 = 3;
+
+/// Values of the `d` field of static imports and `import.meta`,
+/// which is the offset of the opening paren for dynamic imports.
+const STATIC_IMPORT: isize = -1;
+const IMPORT_META: isize = -2;
+
+/// The value of the `a` field, as import attributes are not parsed.
+const NO_ATTRIBUTES: isize = -1;
+
+/// Builds `[imports, exports, facade, hasModuleSyntax]`, with all offsets in UTF-16 code units.
+pub(crate) fn to_compat(source: &str, analysis: &SourceAnalysis) -> CompatResult {
+    let offsets = Utf16Offsets::for_records(source, &analysis.imports, &analysis.exports);
+
+    let imports = analysis
+        .imports
+        .iter()
+        .map(|import| match import {
+            Import::Static(si) => CompatImport {
+                n: Some(unescape(&source[si.module_specifier_range()])),
+                t: IMPORT_TYPE_STATIC,
+                s: offsets.get(si.start),
+                e: offsets.get(si.end),
+                ss: offsets.get(si.statement_start),
+                se: offsets.get(si.statement_end),
+                d: STATIC_IMPORT,
+                a: NO_ATTRIBUTES,
+            },
+            Import::Dynamic(di) => {
+                let expression = &source[di.module_specifier_expression_range()];
+                CompatImport {
+                    n: string_literal(expression.trim()).map(unescape),
+                    t: IMPORT_TYPE_DYNAMIC,
+                    s: offsets.get(di.start),
+                    e: offsets.get(di.end),
+                    ss: offsets.get(di.statement_start),
+                    se: offsets.get(di.end + 1),
+                    // the paren right before the specifier expression
+                    d: offsets.get(di.start) as isize - 1,
+                    a: NO_ATTRIBUTES,
+                }
+            }
+            Import::Meta(im) => CompatImport {
+                n: None,
+                t: IMPORT_TYPE_IMPORT_META,
+                s: offsets.get(im.start),
+                e: offsets.get(im.end),
+                ss: offsets.get(im.start),
+                se: offsets.get(im.end),
+                d: IMPORT_META,
+                a: NO_ATTRIBUTES,
+            },
+        })
+        .collect();
+
+    let exports = analysis
+        .exports
+        .iter()
+        .map(|export| {
+            let (ln, ls, le) = match export.local.clone() {
+                Some(local) => (
+                    Some(source[local.clone()].to_string()),
+                    offsets.get(local.start) as isize,
+                    offsets.get(local.end) as isize,
+                ),
+                None => (None, -1, -1),
+            };
+            CompatExport {
+                n: source[export.export_specifier_range()].to_string(),
+                s: offsets.get(export.start),
+                e: offsets.get(export.end),
+                ln,
+                ls,
+                le,
+            }
+        })
+        .collect();
+
+    CompatResult(
+        imports,
+        exports,
+        analysis.facade,
+        analysis.has_module_syntax,
+    )
+}
+
+/// Parses each source into the result of `parseCompat`, or the error it would throw,
+/// so that one invalid source does not fail the others.
+pub(crate) fn parse_many(
+    sources: &Array,
+    names: Option<&Array>,
+    limits: ParseLimits,
+) -> CompatResults {
+    sources
+        .iter()
+        .enumerate()
+        .map(|(i, source)| {
+            let name = names.and_then(|names| names.get(i as u32).as_string());
+            let source = match source.as_string() {
+                Some(source) => source,
+                None => return JsValue::from(js_sys::TypeError::new("source is not a string")),
+            };
+            match parse_with_limits(&source, limits) {
+                Ok(analysis) => {
+                    JsValue::from(to_compat(&source, &analysis).into_js().unwrap_throw())
+                }
+                Err(err) => error_to_js(&source, name.as_deref(), &err),
+            }
+        })
+        .collect::<Array>()
+        .unchecked_into()
+}
+
+/// An `Error` like the ones thrown by `es-module-lexer`, with the UTF-16 offset of the error as `idx`.
+/// The line and column of its message are one based, with the column in UTF-16 code units.
+pub(crate) fn error_to_js(source: &str, name: Option<&str>, err: &ParseError) -> JsValue {
+    let (line, column) = utf16_line_col(source, err);
+    let js_err = js_sys::Error::new(&format!(
+        "Parse error {}:{}:{}",
+        name.unwrap_or("@"),
+        line,
+        column
+    ));
+    set(
+        &js_err,
+        "idx",
+        JsValue::from_f64(utf16_idx(source, err) as f64),
+    );
+    JsValue::from(js_err)
+}
+
+fn set(object: &Object, key: &str, value: JsValue) {
+    // cannot fail for a plain object
+    Reflect::set(object, &JsValue::from(key), &value).unwrap_throw();
+}
+
+/// The contents of a single or double quoted string literal.
+fn string_literal(expression: &str) -> Option<&str> {
+    let quote = expression
+        .chars()
+        .next()
+        .filter(|&c| c == '\'' || c == '"')?;
+    if expression.len() < 2 || !expression.ends_with(quote) {
+        return None;
+    }
+    let contents = &expression[1..expression.len() - 1];
+    if contents.contains(quote) {
+        None
+    } else {
+        Some(contents)
+    }
+}
+
+/// Decodes the escape sequences of the contents of a string literal.
+///
+/// Lone surrogates cannot be represented and are replaced by U+FFFD.
+fn unescape(contents: &str) -> String {
+    if !contents.contains('\\') {
+        return contents.to_string();
+    }
+    let mut result = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    let mut high_surrogate = None;
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            push_code_unit(&mut result, &mut high_surrogate, None);
+            result.push(c);
+            continue;
+        }
+        let code_point = match chars.next() {
+            Some('n') => '\n' as u32,
+            Some('t') => '\t' as u32,
+            Some('r') => '\r' as u32,
+            Some('b') => '\u{8}' as u32,
+            Some('f') => '\u{c}' as u32,
+            Some('v') => '\u{b}' as u32,
+            Some('0') if !chars.peek().is_some_and(char::is_ascii_digit) => 0,
+            Some('x') => hex(&mut chars, 2),
+            Some('u') if chars.peek() == Some(&'{') => {
+                chars.next();
+                let code_point = hex(&mut chars, 6);
+                chars.next_if_eq(&'}');
+                code_point
+            }
+            Some('u') => hex(&mut chars, 4),
+            // line continuations
+            Some('\r') => {
+                chars.next_if_eq(&'\n');
+                continue;
+            }
+            Some('\n') | Some('\u{2028}') | Some('\u{2029}') => continue,
+            Some(c) => c as u32,
+            None => break,
+        };
+        push_code_unit(&mut result, &mut high_surrogate, Some(code_point));
+    }
+    push_code_unit(&mut result, &mut high_surrogate, None);
+    result
+}
+
+/// Reads up to `max_len` hex digits.
+fn hex(chars: &mut std::iter::Peekable<std::str::Chars>, max_len: usize) -> u32 {
+    let mut value = 0;
+    for _ in 0..max_len {
+        match chars.peek().and_then(|c| c.to_digit(16)) {
+            Some(digit) => {
+                value = value * 16 + digit;
+                chars.next();
+            }
+            None => break,
+        }
+    }
+    value
+}
+
+/// Pushes an escaped code point, pairing up surrogates escaped as `\uD83D\uDE00`.
+/// `None` flushes a pending high surrogate.
+fn push_code_unit(result: &mut String, high_surrogate: &mut Option<u32>, code_point: Option<u32>) {
+    match (high_surrogate.take(), code_point) {
+        (Some(high), Some(low @ 0xdc00..=0xdfff)) => {
+            let code_point = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+            result.push(char::from_u32(code_point).unwrap_or('\u{fffd}'));
+            return;
+        }
+        (Some(_), _) => result.push('\u{fffd}'),
+        (None, _) => {}
+    }
+    match code_point {
+        Some(high @ 0xd800..=0xdbff) => *high_surrogate = Some(high),
+        Some(code_point) => result.push(char::from_u32(code_point).unwrap_or('\u{fffd}')),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tsify::Tsify;
+
+    use super::{string_literal, unescape, CompatExport, CompatImport, CompatResult};
+
+    /// The keys of `es-module-lexer`, which code written against it reads.
+    #[test]
+    fn typings() {
+        assert_eq!(
+            CompatImport::DECL,
+            "export interface CompatImport {\n    n: string | undefined;\n    t: 1 | 2 | 3;\n    s: number;\n    e: number;\n    ss: number;\n    se: number;\n    d: number;\n    a: number;\n}"
+        );
+        assert_eq!(
+            CompatExport::DECL,
+            "export interface CompatExport {\n    n: string;\n    s: number;\n    e: number;\n    ln: string | undefined;\n    ls: number;\n    le: number;\n}"
+        );
+        assert_eq!(
+            CompatResult::DECL,
+            "export type CompatResult = [CompatImport[], CompatExport[], boolean, boolean];"
+        );
+    }
+
+    #[test]
+    fn string_literals() {
+        assert_eq!(string_literal("'./a.js'"), Some("./a.js"));
+        assert_eq!(string_literal("\"./a.js\""), Some("./a.js"));
+        assert_eq!(string_literal("'a' + 'b'"), None);
+        assert_eq!(string_literal("`./a.js`"), None);
+        assert_eq!(string_literal("a"), None);
+        assert_eq!(string_literal("'"), None);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(unescape("./a.js"), "./a.js");
+        assert_eq!(unescape(r"\x61b\u{63}\'\n"), "abc'\n");
+        assert_eq!(unescape("a\\\r\nb\\\nc"), "abc");
+        assert_eq!(unescape(r"\uD83D\uDE00"), "😀");
+        assert_eq!(unescape(r"\uD83Da"), "\u{fffd}a");
+        assert_eq!(unescape(r"\uDE00"), "\u{fffd}");
+        assert_eq!(unescape(r"\0"), "\0");
+    }
+}
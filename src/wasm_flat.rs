@@ -0,0 +1,137 @@
+//! A low-level API passing the source and the analysis through buffers in wasm memory,
+//! instead of copying the source in and creating a JS object per record.
+//!
+//! The results are a flat buffer of `u32`s, with all offsets in UTF-16 code units:
+//!
+//! * a header of `[import count, export count, facade, has module syntax]`,
+//! * `[type, s, e, ss, se]` for each import, with the types of `parseCompat`,
+//! * `[s, e, ls, le]` for each export, with `ls` and `le` set to `u32::MAX`
+//!   when it has no local name.
+#![cfg(feature = "wasm")]
+
+use std::cell::RefCell;
+
+use js_sys::{Uint32Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+use crate::wasm_compat::{
+    error_to_js, IMPORT_TYPE_DYNAMIC, IMPORT_TYPE_IMPORT_META, IMPORT_TYPE_STATIC,
+};
+use crate::wasm_types::Utf16Offsets;
+use crate::{parse_with_limits, Import, ParseLimits, SourceAnalysis};
+
+/// The local offsets of an export without a local name.
+const NO_LOCAL_NAME: u32 = u32::MAX;
+
+#[derive(Default)]
+struct Buffers {
+    source: Vec<u8>,
+    output: Vec<u32>,
+}
+
+thread_local! {
+    static BUFFERS: RefCell<Buffers> = RefCell::new(Buffers::default());
+}
+
+pub(crate) fn source_buffer(len: usize) -> Uint8Array {
+    BUFFERS.with(|buffers| {
+        let source = &mut buffers.borrow_mut().source;
+        source.clear();
+        source.resize(len, 0);
+        // safe as long as the view is not used after wasm memory could have grown,
+        // which is documented on the exported function
+        unsafe { Uint8Array::view_mut_raw(source.as_mut_ptr(), len) }
+    })
+}
+
+pub(crate) fn parse_source_buffer(
+    len: usize,
+    name: Option<&str>,
+    limits: ParseLimits,
+) -> Result<Uint32Array, JsValue> {
+    BUFFERS.with(|buffers| {
+        let Buffers { source, output } = &mut *buffers.borrow_mut();
+        let source = source
+            .get(..len)
+            .and_then(|source| std::str::from_utf8(source).ok())
+            .ok_or_else(|| js_sys::Error::new("source buffer is not valid UTF-8"))?;
+        let analysis =
+            parse_with_limits(source, limits).map_err(|err| error_to_js(source, name, &err))?;
+        write(output, source, &analysis);
+        // see `source_buffer`
+        Ok(unsafe { Uint32Array::view(output) })
+    })
+}
+
+fn write(output: &mut Vec<u32>, source: &str, analysis: &SourceAnalysis) {
+    let offsets = Utf16Offsets::for_records(source, &analysis.imports, &analysis.exports);
+    let offset = |byte_offset: usize| offsets.get(byte_offset) as u32;
+
+    output.clear();
+    output.reserve(4 + 5 * analysis.imports.len() + 4 * analysis.exports.len());
+    output.extend_from_slice(&[
+        analysis.imports.len() as u32,
+        analysis.exports.len() as u32,
+        analysis.facade as u32,
+        analysis.has_module_syntax as u32,
+    ]);
+    for import in &analysis.imports {
+        let record = match import {
+            Import::Static(si) => [
+                IMPORT_TYPE_STATIC,
+                offset(si.start),
+                offset(si.end),
+                offset(si.statement_start),
+                offset(si.statement_end),
+            ],
+            Import::Dynamic(di) => [
+                IMPORT_TYPE_DYNAMIC,
+                offset(di.start),
+                offset(di.end),
+                offset(di.statement_start),
+                offset(di.end + 1),
+            ],
+            Import::Meta(im) => [
+                IMPORT_TYPE_IMPORT_META,
+                offset(im.start),
+                offset(im.end),
+                offset(im.start),
+                offset(im.end),
+            ],
+        };
+        output.extend_from_slice(&record);
+    }
+    for export in &analysis.exports {
+        let (ls, le) = match &export.local {
+            Some(local) => (offset(local.start), offset(local.end)),
+            None => (NO_LOCAL_NAME, NO_LOCAL_NAME),
+        };
+        output.extend_from_slice(&[offset(export.start), offset(export.end), ls, le]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write;
+    use crate::parse;
+
+    #[test]
+    fn flat_buffer() {
+        let source =
+            "// 😀\nimport a from './a.js';\nimport('./b.js');\nexport { a };\nexport * as c from 'd';";
+        let mut output = vec![42];
+        write(&mut output, source, &parse(source).unwrap());
+        #[rustfmt::skip]
+        assert_eq!(
+            output,
+            [
+                3, 2, 0, 1,
+                1, 21, 27, 6, 28,
+                2, 37, 45, 30, 46,
+                1, 82, 83, 62, 84,
+                57, 58, 57, 58,
+                74, 75, u32::MAX, u32::MAX,
+            ]
+        );
+    }
+}
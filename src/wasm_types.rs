@@ -3,117 +3,421 @@
 #![cfg(feature = "wasm")]
 
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
 use std::ops;
+use std::rc::Rc;
+
+use tsify::Tsify;
+
+use crate::{pretty_error, ColumnUnit, LineIndex, ParseLimits};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT_TYPES: &'static str = r#"
+/** An import, discriminated by its `kind`. */
+export type Import = StaticImport | DynamicImport | ImportMeta;
+
+/**
+ * The error thrown by `parse`, see `isParseError`, with the fields of its kind.
+ *
+ * `message` is the error rendered with the offending source lines, `idx` its offset and `column`
+ * its one based column in UTF-16 code units. `line` and `column` are 0 past the end of the source.
+ */
+export type ParseError = Error & ParseErrorKind & {
+  name: 'ParseError';
+  idx: number;
+  line: number;
+  column: number;
+};
+"#;
+
+/// Limits on the resources a single parse may use, which make it throw once exceeded.
+/// All of them are unlimited by default.
+#[derive(Default, Deserialize, Tsify)]
+#[serde(rename_all = "camelCase")]
+#[tsify(from_wasm_abi)]
+pub struct ParseOptions {
+    /// The maximum depth of nested braces, parens and template expressions.
+    #[tsify(optional)]
+    max_nesting_depth: Option<usize>,
+    /// The maximum length of the source in UTF-8 bytes.
+    #[tsify(optional)]
+    max_source_bytes: Option<usize>,
+    /// The maximum number of imports.
+    #[tsify(optional)]
+    max_imports: Option<usize>,
+    /// The maximum number of imports and exports together, not counting regular expressions.
+    #[tsify(optional)]
+    max_records: Option<usize>,
+}
+
+impl ParseOptions {
+    /// The limits of the options, where missing ones are unlimited.
+    pub(crate) fn limits(&self) -> ParseLimits {
+        let unlimited = ParseLimits::default();
+        ParseLimits {
+            max_nesting_depth: self
+                .max_nesting_depth
+                .unwrap_or(unlimited.max_nesting_depth),
+            max_source_bytes: self.max_source_bytes.unwrap_or(unlimited.max_source_bytes),
+            max_imports: self.max_imports.unwrap_or(unlimited.max_imports),
+            max_records: self.max_records.unwrap_or(unlimited.max_records),
+        }
+    }
+}
+
+#[wasm_bindgen(inline_js = "
+export class ParseError extends Error {
+    constructor(message, idx, line, column, kind) {
+        super(message);
+        this.name = 'ParseError';
+        this.idx = idx;
+        this.line = line;
+        this.column = column;
+        Object.assign(this, kind);
+    }
+}
+")]
+extern "C" {
+    #[wasm_bindgen(js_name = "ParseError", extends = js_sys::Error)]
+    type JsParseError;
+
+    #[wasm_bindgen(constructor, js_class = "ParseError")]
+    fn new(message: &str, idx: u32, line: u32, column: u32, kind: &JsValue) -> JsParseError;
+}
+
+/// Creates the `ParseError` thrown by `parse`, with its position in UTF-16 code units.
+pub(crate) fn error_to_js(source: &str, err: &crate::ParseError) -> JsValue {
+    let idx = utf16_idx(source, err);
+    let (line, column) = utf16_line_col(source, err);
+    // `kind` and, for the `LimitExceeded` kind, `limit`
+    let kind = err.kind.into_js().unwrap_throw();
+    JsValue::from(JsParseError::new(
+        &pretty_error(source, err),
+        idx as u32,
+        line as u32,
+        column as u32,
+        &kind,
+    ))
+}
+
+/// The offset of the error in UTF-16 code units.
+pub(crate) fn utf16_idx(source: &str, err: &crate::ParseError) -> usize {
+    source
+        .get(..err.idx)
+        .map_or(err.idx, |prefix| prefix.encode_utf16().count())
+}
+
+/// The one based line and column of the error, with the column in UTF-16 code units,
+/// or `(0, 0)` like `line` and `col` when the error is past the end of the source.
+pub(crate) fn utf16_line_col(source: &str, err: &crate::ParseError) -> (usize, usize) {
+    if err.line == 0 {
+        return (0, 0);
+    }
+    let position = err.position(&LineIndex::new(source), ColumnUnit::Utf16);
+    (position.line + 1, position.col + 1)
+}
+
+/// Whether the value is a `ParseError` thrown by `parse`.
+#[wasm_bindgen(js_name = "isParseError")]
+pub fn is_parse_error(value: &JsValue) -> bool {
+    value.is_instance_of::<JsParseError>()
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Array<Import>")]
+    pub type ImportArray;
+
+    #[wasm_bindgen(typescript_type = "Array<Export>")]
+    pub type ExportArray;
+
+    #[wasm_bindgen(typescript_type = "string[]", extends = js_sys::Array)]
+    pub type StringArray;
+
+    #[wasm_bindgen(typescript_type = "Array<string | undefined>", extends = js_sys::Array)]
+    pub type NameArray;
+
+    #[wasm_bindgen(typescript_type = "'static'")]
+    pub type StaticKind;
+
+    #[wasm_bindgen(typescript_type = "'dynamic'")]
+    pub type DynamicKind;
+
+    #[wasm_bindgen(typescript_type = "'meta'")]
+    pub type MetaKind;
+}
 
 #[wasm_bindgen]
 pub struct SourceAnalysis {
     imports: Vec<super::Import>,
     exports: Vec<super::Export>,
+    offsets: Rc<Utf16Offsets>,
 }
 
 #[wasm_bindgen]
 impl SourceAnalysis {
     pub(crate) fn from_imports_and_exports(
+        source: &str,
         imports: Vec<super::Import>,
         exports: Vec<super::Export>,
     ) -> Self {
-        SourceAnalysis { imports, exports }
+        let offsets = Rc::new(Utf16Offsets::for_records(source, &imports, &exports));
+        SourceAnalysis {
+            imports,
+            exports,
+            offsets,
+        }
     }
 
     #[wasm_bindgen(getter)]
-    pub fn imports(&self) -> js_sys::Array {
+    pub fn imports(&self) -> ImportArray {
         self.imports
             .iter()
             .cloned()
             .map(|import| match import {
-                super::Import::Static(si) => JsValue::from(StaticImport { inner: si }),
-                super::Import::Dynamic(di) => JsValue::from(DynamicImport { inner: di }),
-                super::Import::Meta(im) => JsValue::from(ImportMeta { inner: im }),
+                super::Import::Static(si) => JsValue::from(StaticImport {
+                    inner: si,
+                    offsets: self.offsets.clone(),
+                }),
+                super::Import::Dynamic(di) => JsValue::from(DynamicImport {
+                    inner: di,
+                    offsets: self.offsets.clone(),
+                }),
+                super::Import::Meta(im) => JsValue::from(ImportMeta {
+                    inner: im,
+                    offsets: self.offsets.clone(),
+                }),
             })
-            .collect()
+            .collect::<js_sys::Array>()
+            .unchecked_into()
     }
 
     #[wasm_bindgen(getter)]
-    pub fn exports(&self) -> js_sys::Array {
+    pub fn exports(&self) -> ExportArray {
         self.exports
             .iter()
             .cloned()
-            .map(|ex| JsValue::from(Export { inner: ex }))
-            .collect()
+            .map(|ex| {
+                JsValue::from(Export {
+                    inner: ex,
+                    offsets: self.offsets.clone(),
+                })
+            })
+            .collect::<js_sys::Array>()
+            .unchecked_into()
     }
 }
 
 #[wasm_bindgen]
 pub struct StaticImport {
     inner: super::StaticImport,
+    offsets: Rc<Utf16Offsets>,
 }
 
 #[wasm_bindgen]
 impl StaticImport {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> StaticKind {
+        JsValue::from("static").unchecked_into()
+    }
+
     #[wasm_bindgen(js_name = "moduleSpecifierRange")]
     pub fn module_specifier_range(&self) -> Range {
-        let ops::Range { start, end } = self.inner.module_specifier_range();
-        Range { start, end }
+        self.offsets.range(self.inner.module_specifier_range())
     }
 
     #[wasm_bindgen(js_name = "statementRange")]
     pub fn statement_range(&self) -> Range {
-        let ops::Range { start, end } = self.inner.statement_range();
-        Range { start, end }
+        self.offsets.range(self.inner.statement_range())
     }
 }
 
 #[wasm_bindgen]
 pub struct DynamicImport {
     inner: super::DynamicImport,
+    offsets: Rc<Utf16Offsets>,
 }
 
 #[wasm_bindgen]
 impl DynamicImport {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> DynamicKind {
+        JsValue::from("dynamic").unchecked_into()
+    }
+
     #[wasm_bindgen(js_name = "moduleSpecifierExpressionRange")]
     pub fn module_specifier_expression_range(&self) -> Range {
-        let ops::Range { start, end } = self.inner.module_specifier_expression_range();
-        Range { start, end }
+        self.offsets
+            .range(self.inner.module_specifier_expression_range())
     }
 
     #[wasm_bindgen(js_name = "importExpressionRange")]
     pub fn import_expression_range(&self) -> Range {
-        let ops::Range { start, end } = self.inner.import_expression_range();
-        Range { start, end }
+        self.offsets.range(self.inner.import_expression_range())
     }
 }
 
 #[wasm_bindgen]
 pub struct ImportMeta {
     inner: super::ImportMeta,
+    offsets: Rc<Utf16Offsets>,
 }
 
 #[wasm_bindgen]
 impl ImportMeta {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> MetaKind {
+        JsValue::from("meta").unchecked_into()
+    }
+
     #[wasm_bindgen(js_name = "expressionRange")]
     pub fn expression_range(&self) -> Range {
-        let ops::Range { start, end } = self.inner.expression_range();
-        Range { start, end }
+        self.offsets.range(self.inner.expression_range())
     }
 }
 
 #[wasm_bindgen]
 pub struct Export {
     inner: super::Export,
+    offsets: Rc<Utf16Offsets>,
 }
 
 #[wasm_bindgen]
 impl Export {
     #[wasm_bindgen(js_name = "exportSpecifierRange")]
     pub fn export_specifier_range(&self) -> Range {
-        let ops::Range { start, end } = self.inner.export_specifier_range();
-        Range { start, end }
+        self.offsets.range(self.inner.export_specifier_range())
     }
 }
 
+/// A range of the source in UTF-16 code units, as used by `String.prototype.substring`,
+/// along with the same range in UTF-8 bytes.
 #[wasm_bindgen]
+#[derive(Clone, Copy)]
 pub struct Range {
     pub start: usize,
     pub end: usize,
+    #[wasm_bindgen(js_name = "byteStart")]
+    pub byte_start: usize,
+    #[wasm_bindgen(js_name = "byteEnd")]
+    pub byte_end: usize,
+}
+
+/// Maps the byte offsets of the ranges in an analysis to UTF-16 offsets.
+///
+/// The offsets are converted in a single pass over the source when it is built,
+/// which is skipped entirely for ASCII sources where both offsets are the same.
+pub(crate) struct Utf16Offsets {
+    /// Sorted pairs of (byte offset, UTF-16 offset), empty for ASCII sources.
+    offsets: Vec<(usize, usize)>,
+}
+
+impl Utf16Offsets {
+    /// Converts the start and end offsets of all ranges of the imports and exports,
+    /// including the local names of the exports.
+    pub(crate) fn for_records(
+        source: &str,
+        imports: &[super::Import],
+        exports: &[super::Export],
+    ) -> Self {
+        let mut byte_offsets = Vec::with_capacity(4 * imports.len() + 4 * exports.len());
+        for import in imports {
+            let (a, b) = match import {
+                super::Import::Static(si) => (si.module_specifier_range(), si.statement_range()),
+                super::Import::Dynamic(di) => (
+                    di.module_specifier_expression_range(),
+                    di.import_expression_range(),
+                ),
+                super::Import::Meta(im) => (im.expression_range(), im.expression_range()),
+            };
+            byte_offsets.extend_from_slice(&[a.start, a.end, b.start, b.end]);
+        }
+        for export in exports {
+            let range = export.export_specifier_range();
+            byte_offsets.extend_from_slice(&[range.start, range.end]);
+            if let Some(local) = &export.local {
+                byte_offsets.extend_from_slice(&[local.start, local.end]);
+            }
+        }
+        Utf16Offsets::new(source, byte_offsets)
+    }
+
+    fn new(source: &str, mut byte_offsets: Vec<usize>) -> Self {
+        if source.is_ascii() {
+            return Utf16Offsets {
+                offsets: Vec::new(),
+            };
+        }
+        byte_offsets.sort_unstable();
+        byte_offsets.dedup();
+        let mut offsets = Vec::with_capacity(byte_offsets.len());
+        let mut byte_offsets = byte_offsets.into_iter().peekable();
+        let mut utf16_offset = 0;
+        for (byte_offset, c) in source.char_indices() {
+            while let Some(offset) = byte_offsets.next_if(|&offset| offset <= byte_offset) {
+                offsets.push((offset, utf16_offset));
+            }
+            utf16_offset += c.len_utf16();
+        }
+        offsets.extend(byte_offsets.map(|offset| (offset, utf16_offset)));
+        Utf16Offsets { offsets }
+    }
+
+    /// The UTF-16 offset of one of the byte offsets the map was built for.
+    pub(crate) fn get(&self, byte_offset: usize) -> usize {
+        match self
+            .offsets
+            .binary_search_by_key(&byte_offset, |&(byte, _)| byte)
+        {
+            Ok(idx) => self.offsets[idx].1,
+            Err(_) => byte_offset,
+        }
+    }
+
+    fn range(&self, range: ops::Range<usize>) -> Range {
+        Range {
+            start: self.get(range.start),
+            end: self.get(range.end),
+            byte_start: range.start,
+            byte_end: range.end,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tsify::Tsify;
+
+    use super::{ParseOptions, Utf16Offsets};
+    use crate::{Limit, ParseErrorKind};
+
+    #[test]
+    fn typings() {
+        assert_eq!(
+            ParseOptions::DECL,
+            "export interface ParseOptions {\n    maxNestingDepth?: number;\n    maxSourceBytes?: number;\n    maxImports?: number;\n    maxRecords?: number;\n}"
+        );
+        assert!(ParseErrorKind::DECL
+            .starts_with("export type ParseErrorKind = { kind: \"UnterminatedString\" } | "));
+        assert!(ParseErrorKind::DECL.ends_with("| { kind: \"LimitExceeded\"; limit: Limit };"));
+        assert_eq!(
+            Limit::DECL,
+            "export type Limit = \"NestingDepth\" | \"SourceBytes\" | \"Imports\" | \"Records\";"
+        );
+    }
+
+    #[test]
+    fn utf16_offsets() {
+        let source = "// 😀 日本\nimport 'a';";
+        let specifier = source.find('a').unwrap();
+        let offsets = Utf16Offsets::new(source, vec![source.len(), specifier, 0]);
+        assert_eq!(offsets.get(0), 0);
+        assert_eq!(offsets.get(specifier), 17);
+        assert_eq!(offsets.get(source.len()), source.encode_utf16().count());
+
+        let offsets = Utf16Offsets::new("import 'a';", vec![8]);
+        assert!(offsets.offsets.is_empty());
+        assert_eq!(offsets.get(8), 8);
+    }
 }
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use es_module_lexer::{parse, parse_bytes, Import, ParseErrorKind, StaticImport};
+
+    fn static_imports(source: &[u8]) -> Vec<StaticImport> {
+        parse_bytes(source)
+            .unwrap()
+            .imports
+            .into_iter()
+            .map(|import| match import {
+                Import::Static(i) => i,
+                _ => panic!("Expected Import::Static"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_as_parse_for_utf8() {
+        let source = "import a from './é.js';\nexport const b = /ü/u;";
+        let from_str = parse(source).unwrap();
+        let from_bytes = parse_bytes(source.as_bytes()).unwrap();
+        assert_eq!(from_str.exports, from_bytes.exports);
+        assert_eq!(from_str.imports.len(), from_bytes.imports.len());
+        assert_eq!(
+            from_str.regular_expressions.len(),
+            from_bytes.regular_expressions.len()
+        );
+    }
+
+    #[test]
+    fn latin1_source() {
+        // "// © 2011\nimport 'caf\xe9.js';\nexport var d\xe9j\xe0;" in Latin-1
+        let source = b"// \xa9 2011\nimport 'caf\xe9.js';\nexport var d\xe9j\xe0;";
+        let imports = static_imports(source);
+        assert_eq!(imports.len(), 1);
+        assert_eq!(&source[imports[0].module_specifier_range()], b"caf\xe9.js");
+        let exports = parse_bytes(source).unwrap().exports;
+        assert_eq!(&source[exports[0].export_specifier_range()], b"d\xe9j\xe0");
+    }
+
+    #[test]
+    fn specifier_decoding() {
+        let source = b"import './a.js';\nimport './\xff\xfe.js';";
+        let imports = static_imports(source);
+        assert_eq!(imports[0].module_specifier(source).unwrap(), "./a.js");
+        let err = imports[1].module_specifier(source).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidUtf8);
+        assert_eq!(err.range, 27..28);
+        assert_eq!((err.line, err.col), (2, 11));
+
+        let source = b"export { a, b\xc3 }";
+        let exports = parse_bytes(source).unwrap().exports;
+        assert_eq!(exports[0].name(source).unwrap(), "a");
+        let err = exports[1].name(source).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidUtf8);
+        assert_eq!(err.range, 13..14);
+    }
+
+    #[test]
+    fn decoding_against_another_source() {
+        let source = b"import './a.js';";
+        let imports = static_imports(source);
+        let err = imports[0].module_specifier(b"").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEndOfInput);
+    }
+
+    #[test]
+    fn errors_in_invalid_utf8() {
+        let source = b"\xff\xfe;\n\x80 '\xff";
+        let err = parse_bytes(source).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnterminatedString);
+        assert_eq!(err.range, 6..8);
+        assert_eq!((err.line, err.col), (2, 3));
+    }
+}
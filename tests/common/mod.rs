@@ -0,0 +1,69 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+pub mod ranges;
+
+use std::ops::ControlFlow;
+
+use es_module_lexer::{
+    parse_with_visitor, DynamicImport, Export, ImportMeta, ModuleVisitor, ParseError,
+    RegularExpression, StaticImport,
+};
+
+/// Records the callbacks in the order they are made, as the `Debug` output of their records.
+#[derive(Default)]
+pub struct Recorder {
+    pub calls: Vec<String>,
+    /// Stops lexing once this many callbacks were made.
+    pub stop_after: Option<usize>,
+}
+
+impl Recorder {
+    pub fn stopping_after(calls: usize) -> Recorder {
+        Recorder {
+            stop_after: Some(calls),
+            ..Recorder::default()
+        }
+    }
+
+    fn record(&mut self, call: String) -> ControlFlow<()> {
+        self.calls.push(call);
+        if Some(self.calls.len()) == self.stop_after {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+impl ModuleVisitor for Recorder {
+    fn on_static_import(&mut self, import: StaticImport) -> ControlFlow<()> {
+        self.record(format!("{:?}", import))
+    }
+
+    fn on_dynamic_import(&mut self, import: DynamicImport) -> ControlFlow<()> {
+        self.record(format!("{:?}", import))
+    }
+
+    fn on_import_meta(&mut self, import: ImportMeta) -> ControlFlow<()> {
+        self.record(format!("{:?}", import))
+    }
+
+    fn on_export(&mut self, export: Export) -> ControlFlow<()> {
+        self.record(format!("{:?}", export))
+    }
+
+    fn on_regular_expression(&mut self, regex: RegularExpression) -> ControlFlow<()> {
+        self.record(format!("{:?}", regex))
+    }
+}
+
+/// The callbacks of [`parse_with_visitor`] on the whole source, which lexing it in parts must match.
+pub fn record(source: &str, stop_after: Option<usize>) -> (Vec<String>, Result<(), ParseError>) {
+    let mut recorder = Recorder {
+        stop_after,
+        ..Recorder::default()
+    };
+    let result = parse_with_visitor(source, &mut recorder);
+    (recorder.calls, result)
+}
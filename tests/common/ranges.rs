@@ -0,0 +1,33 @@
+//! A check shared by the panic tests and the fuzz target, which includes this file as well.
+
+use es_module_lexer::{Import, SourceAnalysis};
+
+/// Slices every range of the analysis, which panics if any of them is out of bounds.
+pub fn check_ranges(source: &str, analysis: &SourceAnalysis) {
+    for import in &analysis.imports {
+        match import {
+            Import::Static(i) => {
+                let _ = &source[i.statement_range()];
+                let _ = &source[i.module_specifier_range()];
+            }
+            Import::Dynamic(i) => {
+                let _ = &source[i.import_expression_range()];
+                let _ = &source[i.module_specifier_expression_range()];
+            }
+            Import::Meta(i) => {
+                let _ = &source[i.expression_range()];
+            }
+        }
+    }
+    for export in &analysis.exports {
+        let _ = &source[export.export_specifier_range()];
+        if let Some(local) = &export.local {
+            let _ = &source[local.clone()];
+        }
+    }
+    for re in &analysis.regular_expressions {
+        let _ = &source[re.expression_range()];
+        let _ = &source[re.pattern_range()];
+        let _ = &source[re.flags_range()];
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use es_module_lexer::parse;
+
+    fn regular_expressions(source: &str) -> Vec<&str> {
+        parse(source)
+            .unwrap()
+            .regular_expressions
+            .iter()
+            .map(|re| &source[re.expression_range()])
+            .collect()
+    }
+
+    #[test]
+    fn keywords() {
+        let cases: &[(&str, &[&str])] = &[
+            ("for (const a of /x/g.exec(b)) {}", &["/x/g"]),
+            ("of / 2 / 3", &[]),
+            ("for (of of /x/) {}", &["/x/"]),
+            ("class A extends /x/.constructor {}", &["/x/"]),
+            ("function* f() { yield /x/ }", &["/x/"]),
+            ("function* f() { yield* /x/ }", &["/x/"]),
+            ("let a = b / c / d", &[]),
+            ("typeof /x/", &["/x/"]),
+            ("a instanceof /x/.constructor", &["/x/"]),
+            ("async function f() { await /x/ }", &["/x/"]),
+            ("x = a.return / 2 / 3", &[]),
+            ("x = a?.typeof / 2 / 3", &[]),
+            ("x = a.in / 2 / 3", &[]),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(&regular_expressions(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn increments() {
+        let cases: &[(&str, &[&str])] = &[
+            ("a++ / 2 / 3", &[]),
+            ("a-- / 2 / 3", &[]),
+            ("(a)++ / 2 / 3", &[]),
+            ("x = ++/x/.lastIndex", &["/x/"]),
+            ("x = a + ++/x/.lastIndex", &["/x/"]),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(&regular_expressions(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn closing_braces() {
+        let cases: &[(&str, &[&str])] = &[
+            ("function f() {}\n/x/.test(a)", &["/x/"]),
+            ("x = function () {} / 2 / 3", &[]),
+            ("x = class {} / 2 / 3", &[]),
+            ("class A {}\n/x/.test(a)", &["/x/"]),
+            ("x = {} / 2 / 3", &[]),
+            ("{}\n/x/.test(a)", &["/x/"]),
+            ("if (a) {}\n/x/.test(a)", &["/x/"]),
+            ("x = () => {}\n/x/.test(a)", &["/x/"]),
+            ("export function f() {}\n/x/.test(a)", &["/x/"]),
+            ("export default class {}\n/x/.test(a)", &["/x/"]),
+            ("x = a ? {} / 2 : /x/", &["/x/"]),
+            ("`${a}` / 2 / 3", &[]),
+            ("`${ {} / 2 / 3 }`", &[]),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(&regular_expressions(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn closing_parens() {
+        let cases: &[(&str, &[&str])] = &[
+            ("if (a) /x/.test(b)", &["/x/"]),
+            ("while (a) /x/.test(b)", &["/x/"]),
+            ("for (;;) /x/.test(b)", &["/x/"]),
+            ("f(a) / 2 / 3", &[]),
+            ("x = (a) / 2 / 3", &[]),
+            ("a.if(b) / 2 / 3", &[]),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(&regular_expressions(source), expected, "{}", source);
+        }
+    }
+}
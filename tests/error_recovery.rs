@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use es_module_lexer::{parse_with_recovery, Import, ParseError, ParseErrorKind};
+
+    fn recover(source: &str) -> (Vec<&str>, Vec<ParseErrorKind>) {
+        let (analysis, errors) = parse_with_recovery(source);
+        let specifiers = analysis
+            .imports
+            .iter()
+            .map(|import| match import {
+                Import::Static(i) => &source[i.module_specifier_range()],
+                Import::Dynamic(i) => &source[i.module_specifier_expression_range()],
+                Import::Meta(_) => "import.meta",
+            })
+            .collect();
+        (specifiers, errors.iter().map(|err| err.kind).collect())
+    }
+
+    #[test]
+    fn valid_source() {
+        let (specifiers, errors) = recover("import a from './a.js';\nimport('./b.js');");
+        assert_eq!(specifiers, ["./a.js", "'./b.js'"]);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn unterminated_strings() {
+        let source = "import a from './a.js';\nconst s = 'abc\nimport b from './b.js';";
+        let (specifiers, errors) = recover(source);
+        assert_eq!(specifiers, ["./a.js", "./b.js"]);
+        assert_eq!(errors, [ParseErrorKind::UnterminatedString]);
+
+        let source = "import a from './a.js\nimport b from \"./b.js\";";
+        let (specifiers, errors) = recover(source);
+        assert_eq!(specifiers, ["./b.js"]);
+        assert_eq!(errors, [ParseErrorKind::UnterminatedString]);
+    }
+
+    #[test]
+    fn unterminated_regular_expressions() {
+        let source = "x = /abc\nimport b from './b.js';";
+        let (specifiers, errors) = recover(source);
+        assert_eq!(specifiers, ["./b.js"]);
+        assert_eq!(errors, [ParseErrorKind::UnterminatedRegex]);
+    }
+
+    #[test]
+    fn invalid_statements() {
+        let source = "import a from './a.js';\nexport { a = b };\nexport * from c\nimport b from './b.js';\nexport { b };";
+        let (analysis, errors) = parse_with_recovery(source);
+        assert_eq!(analysis.imports.len(), 2);
+        let exports: Vec<_> = analysis
+            .exports
+            .iter()
+            .map(|export| &source[export.export_specifier_range()])
+            .collect();
+        assert_eq!(exports, ["a", "b"]);
+        let kinds: Vec<_> = errors.iter().map(|err| err.kind).collect();
+        assert_eq!(
+            kinds,
+            [ParseErrorKind::InvalidExport, ParseErrorKind::InvalidExport]
+        );
+    }
+
+    #[test]
+    fn statement_boundaries() {
+        // the `;` in the parens and the line breaks in the braces are inside the invalid statements
+        let source = "export { a = (b; import('./x.js')) };\nexport { c,\n  d e,\n  f = import('./y.js') }\nimport g from './g.js';";
+        let (specifiers, errors) = recover(source);
+        assert_eq!(specifiers, ["./g.js"]);
+        assert_eq!(
+            errors,
+            [ParseErrorKind::InvalidExport, ParseErrorKind::InvalidExport]
+        );
+    }
+
+    #[test]
+    fn unbalanced_brackets() {
+        let source = "function f() {\n  g(a;\n}\nimport('./a.js');";
+        let (specifiers, errors) = recover(source);
+        assert_eq!(specifiers, ["'./a.js'"]);
+        assert_eq!(errors, [ParseErrorKind::UnbalancedBracket]);
+
+        let source = "a);\nimport('./a.js');";
+        let (specifiers, errors) = recover(source);
+        assert_eq!(specifiers, ["'./a.js'"]);
+        assert_eq!(errors, [ParseErrorKind::UnbalancedBracket]);
+    }
+
+    #[test]
+    fn unclosed_at_end_of_input() {
+        let source = "import a from './a.js';\nif (a) {\n  import('./b.js');\n  import('./c.js'";
+        let (specifiers, errors) = recover(source);
+        assert_eq!(specifiers, ["./a.js", "'./b.js'"]);
+        assert_eq!(errors, [ParseErrorKind::UnbalancedBracket]);
+
+        let (specifiers, errors) = recover("import './a.js';\n/* abc");
+        assert_eq!(specifiers, ["./a.js"]);
+        assert_eq!(errors, [ParseErrorKind::UnterminatedBlockComment]);
+    }
+
+    #[test]
+    fn error_positions() {
+        let source = "a = 'b\r\n\u{2028}c);\n".repeat(1000);
+        let (_, errors) = parse_with_recovery(&source);
+        assert_eq!(errors.len(), 2000);
+        for err in errors {
+            let expected = ParseError::new(&source, err.kind, err.range.clone());
+            assert_eq!((err.line, err.col), (expected.line, expected.col));
+        }
+    }
+}
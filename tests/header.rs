@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use std::fs::{read_dir, read_to_string};
+
+    use es_module_lexer::{parse, parse_header, Import, ParseErrorKind};
+
+    fn specifiers(source: &str) -> (Vec<&str>, bool) {
+        let header = parse_header(source).unwrap();
+        let specifiers = header
+            .imports
+            .iter()
+            .map(|import| &source[import.module_specifier_range()])
+            .collect();
+        (specifiers, header.may_contain_more)
+    }
+
+    #[test]
+    fn whole_source() {
+        let source = "#!/usr/bin/env node\n'use strict';\n/* a */ import a from './a.js'\n\
+                      // b\nexport * from './b.js';\nexport { c } from './c.js';;";
+        assert_eq!(
+            specifiers(source),
+            (vec!["./a.js", "./b.js", "./c.js"], false)
+        );
+        assert_eq!(specifiers(""), (vec![], false));
+    }
+
+    #[test]
+    fn stops_after_header() {
+        let cases = [
+            (
+                "import './a.js';\nconst b = 1;\nimport './c.js';",
+                vec!["./a.js"],
+            ),
+            ("import './a.js';\nimport('./b.js');", vec!["./a.js"]),
+            ("import './a.js';\nimport.meta.url;", vec!["./a.js"]),
+            ("import './a.js';\nexport const b = 1;", vec!["./a.js"]),
+            ("import './a.js';\nexport { b };", vec!["./a.js"]),
+            ("'use' + strict;\nimport './a.js';", vec![]),
+            ("/a/.test(b);\nimport './a.js';", vec![]),
+            ("{}\nimport './a.js';", vec![]),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(specifiers(source), (expected, true), "{}", source);
+        }
+    }
+
+    #[test]
+    fn errors_after_header_are_ignored() {
+        assert_eq!(
+            specifiers("import './a.js';\nf({ b: '"),
+            (vec!["./a.js"], true)
+        );
+        let err = parse_header("import './a.js").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnterminatedString);
+    }
+
+    #[test]
+    fn matches_parse() {
+        for entry in read_dir("fixtures").unwrap() {
+            let source = read_to_string(entry.unwrap().path()).unwrap();
+            let header = parse_header(&source).unwrap();
+            let analysis = parse(&source).unwrap();
+            let leading = analysis
+                .imports
+                .iter()
+                .take(header.imports.len())
+                .map(|import| match import {
+                    Import::Static(import) => import.statement_range(),
+                    _ => panic!("Expected static import"),
+                });
+            assert!(header
+                .imports
+                .iter()
+                .map(|import| import.statement_range())
+                .eq(leading));
+        }
+    }
+}
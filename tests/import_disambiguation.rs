@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use es_module_lexer::{parse, DynamicImport, Import};
+
+    fn dynamic_imports(source: &str) -> Vec<DynamicImport> {
+        parse(source)
+            .unwrap()
+            .imports
+            .into_iter()
+            .map(|import| match import {
+                Import::Dynamic(i) => i,
+                _ => panic!("Expected Import::Dynamic"),
+            })
+            .collect()
+    }
+
+    fn specifiers(source: &str) -> Vec<&str> {
+        dynamic_imports(source)
+            .iter()
+            .map(|i| &source[i.module_specifier_expression_range()])
+            .collect()
+    }
+
+    #[test]
+    fn class_methods() {
+        assert!(specifiers("class A { import(a) { } }").is_empty());
+        assert!(specifiers("class A { static import(a) { } }").is_empty());
+        assert!(specifiers("class A { get import() { } set import(v) { } }").is_empty());
+        assert!(specifiers("class A { async import() { } }").is_empty());
+        assert!(specifiers("class A { a() {} import() {} }").is_empty());
+        assert!(specifiers("class A { a = 1; import() {} }").is_empty());
+        assert!(specifiers("class A extends B { import() { return super.import() } }").is_empty());
+    }
+
+    #[test]
+    fn class_field_initializers() {
+        let source = "class A { a = import('./a.js'); b = () => import('./b.js') }";
+        assert_eq!(specifiers(source), ["'./a.js'", "'./b.js'"]);
+    }
+
+    #[test]
+    fn object_methods() {
+        assert!(specifiers("({ import() {} })").is_empty());
+        assert!(specifiers("x = { a: 1, import(b) { return b } }").is_empty());
+        assert!(specifiers("x = { get import() { return 1 } }").is_empty());
+        assert!(specifiers("x = { async import() {} }").is_empty());
+        assert!(specifiers("f({\n  import /* method */ (a) {}\n})").is_empty());
+    }
+
+    #[test]
+    fn object_properties() {
+        assert!(specifiers("x = { import: 1 }").is_empty());
+        assert!(specifiers("x = { import }").is_empty());
+        let source = "x = { import: import('./a.js'), b: () => import('./b.js') }";
+        assert_eq!(specifiers(source), ["'./a.js'", "'./b.js'"]);
+        let source = "x = { a: await import('./a.js') }";
+        assert_eq!(specifiers(source), ["'./a.js'"]);
+    }
+
+    #[test]
+    fn member_calls() {
+        assert!(specifiers("obj.import(x)").is_empty());
+        assert!(specifiers("obj?.import(x)").is_empty());
+        assert!(specifiers("obj.\n import(x)").is_empty());
+        assert!(specifiers("obj\n  // comment\n  .import(x)").is_empty());
+        assert!(specifiers("obj. /* comment */ import(x)").is_empty());
+    }
+
+    #[test]
+    fn block_statements() {
+        let source =
+            "{ import('./a.js') }\nif (a) { import('./b.js'); }\nfunction f() { import('./c.js') }";
+        assert_eq!(specifiers(source), ["'./a.js'", "'./b.js'", "'./c.js'"]);
+        let source = "label: { import('./a.js') }\nswitch (a) { case 1: { import('./b.js') } }";
+        assert_eq!(specifiers(source), ["'./a.js'", "'./b.js'"]);
+    }
+
+    #[test]
+    fn expression_positions() {
+        let source = "[...import('./a.js')]; f(a, import('./b.js')); a ? import('./c.js') : {}";
+        assert_eq!(specifiers(source), ["'./a.js'", "'./b.js'", "'./c.js'"]);
+        let source = "`${ import('./a.js') }`; ({ a: [import('./b.js')] })";
+        assert_eq!(specifiers(source), ["'./a.js'", "'./b.js'"]);
+        assert_eq!(specifiers("import('./a.js')"), ["'./a.js'"]);
+    }
+
+    #[test]
+    fn nested_dynamic_imports() {
+        let source = "import(foo(import(bar('a'))))";
+        let imports = dynamic_imports(source);
+        assert_eq!(imports.len(), 2);
+        assert_eq!(
+            &source[imports[0].import_expression_range()],
+            "import(foo(import(bar('a'))))"
+        );
+        assert_eq!(
+            &source[imports[1].import_expression_range()],
+            "import(bar('a'))"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use std::fs::{read_dir, read_to_string};
+
+    use es_module_lexer::{parse, parse_with_recovery, Lexer, Limit, ParseErrorKind, ParseLimits};
+
+    #[test]
+    fn reuse_matches_parse() {
+        let mut lexer = Lexer::new();
+        for entry in read_dir("fixtures").unwrap() {
+            let source = read_to_string(entry.unwrap().path()).unwrap();
+            // twice, so that the second parse starts from buffers with results in them
+            for _ in 0..2 {
+                let analysis = lexer.parse(&source).unwrap();
+                assert_eq!(
+                    format!("{:?}", analysis),
+                    format!("{:?}", parse(&source).unwrap())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reset_between_sources() {
+        let mut lexer = Lexer::new();
+        assert!(lexer.parse("import('./a.js'); f({").is_err());
+
+        let analysis = lexer.parse("export * from './b.js';").unwrap();
+        assert_eq!(analysis.imports.len(), 1);
+        assert_eq!(analysis.exports.len(), 0);
+        assert!(analysis.facade);
+        assert!(analysis.has_module_syntax);
+
+        let analysis = lexer.parse("a = /b/;").unwrap();
+        assert!(analysis.imports.is_empty());
+        assert_eq!(analysis.regular_expressions.len(), 1);
+        assert!(!analysis.facade);
+        assert!(!analysis.has_module_syntax);
+    }
+
+    #[test]
+    fn recovery() {
+        let mut lexer = Lexer::new();
+        let source = "import a from './a.js\nimport b from './b.js';\nexport { c = d };";
+        let (expected_analysis, expected_errors) = parse_with_recovery(source);
+        let (analysis, errors) = lexer.parse_with_recovery(source);
+        assert_eq!(
+            format!("{:?}", analysis),
+            format!("{:?}", expected_analysis)
+        );
+        assert_eq!(format!("{:?}", errors), format!("{:?}", expected_errors));
+        assert_eq!(errors.len(), 2);
+
+        let (analysis, errors) = lexer.parse_with_recovery("import './c.js';");
+        assert_eq!(analysis.imports.len(), 1);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn limits() {
+        let mut lexer = Lexer::with_limits(ParseLimits {
+            max_imports: 1,
+            ..ParseLimits::default()
+        });
+        assert!(lexer.parse("import './a.js';").is_ok());
+        let err = lexer
+            .parse("import './a.js';\nimport './b.js';")
+            .unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::LimitExceeded(Limit::Imports));
+        assert!(lexer.parse("import './a.js';").is_ok());
+    }
+}
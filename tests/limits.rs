@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests {
+    use es_module_lexer::{parse, parse_with_limits, Limit, ParseErrorKind, ParseLimits};
+
+    fn limit_exceeded(source: &str, limits: ParseLimits) -> Option<Limit> {
+        match parse_with_limits(source, limits) {
+            Err(err) => match err.kind {
+                ParseErrorKind::LimitExceeded(limit) => Some(limit),
+                kind => panic!("unexpected error {:?}", kind),
+            },
+            Ok(_) => None,
+        }
+    }
+
+    #[test]
+    fn default_is_unlimited() {
+        let source = "(".repeat(1000) + &")".repeat(1000);
+        assert!(parse_with_limits(&source, ParseLimits::default()).is_ok());
+        assert!(parse(&source).is_ok());
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let limits = ParseLimits {
+            max_nesting_depth: 3,
+            ..ParseLimits::default()
+        };
+        assert_eq!(limit_exceeded("f({ a: [`${b}`] })", limits), None);
+        assert_eq!(
+            limit_exceeded("f({ a: `${(b)}` })", limits),
+            Some(Limit::NestingDepth)
+        );
+        let source = "{".repeat(100_000);
+        let err = parse_with_limits(&source, limits).unwrap_err();
+        assert_eq!(err.range, 3..4);
+    }
+
+    #[test]
+    fn source_bytes() {
+        let limits = ParseLimits {
+            max_source_bytes: 10,
+            ..ParseLimits::default()
+        };
+        assert_eq!(limit_exceeded("import 'a'", limits), None);
+        assert_eq!(
+            limit_exceeded("import 'ab'", limits),
+            Some(Limit::SourceBytes)
+        );
+    }
+
+    #[test]
+    fn imports() {
+        let limits = ParseLimits {
+            max_imports: 2,
+            ..ParseLimits::default()
+        };
+        assert_eq!(limit_exceeded("import 'a'; import('b')", limits), None);
+        let source = "import 'a'; import('b'); import.meta";
+        assert_eq!(limit_exceeded(source, limits), Some(Limit::Imports));
+    }
+
+    #[test]
+    fn records() {
+        let limits = ParseLimits {
+            max_records: 3,
+            ..ParseLimits::default()
+        };
+        assert_eq!(limit_exceeded("import 'a'; export { b, c }", limits), None);
+        let source = "import 'a'; export { b, c }; export { d }";
+        assert_eq!(limit_exceeded(source, limits), Some(Limit::Records));
+    }
+
+    #[test]
+    fn regular_expressions_are_not_records() {
+        let limits = ParseLimits {
+            max_records: 3,
+            ..ParseLimits::default()
+        };
+        let source = "import 'a'; export { b, c }; /d/.test(e) && /f/g.exec(g);";
+        assert_eq!(limit_exceeded(source, limits), None);
+    }
+}
@@ -0,0 +1,98 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{read_dir, read_to_string};
+
+    use es_module_lexer::{parse, parse_with_recovery, pretty_error};
+
+    use crate::common::ranges::check_ranges;
+
+    fn check(source: &str) {
+        match parse(source) {
+            Ok(analysis) => check_ranges(source, &analysis),
+            Err(err) => {
+                pretty_error(source, &err);
+            }
+        }
+        let (analysis, errors) = parse_with_recovery(source);
+        check_ranges(source, &analysis);
+        for err in &errors {
+            pretty_error(source, err);
+        }
+    }
+
+    #[test]
+    fn edge_inputs() {
+        let sources = [
+            "",
+            "/",
+            "/*",
+            "//",
+            "'",
+            "\"",
+            "`",
+            "`$",
+            "`${",
+            "`${}",
+            "}",
+            ")",
+            "export",
+            "export ",
+            "export/",
+            "export d",
+            "export default",
+            "export async",
+            "export function",
+            "export class",
+            "export c",
+            "export l",
+            "export var",
+            "export {",
+            "export { a",
+            "export { a as",
+            "export *",
+            "export * as",
+            "export {} from",
+            "import",
+            "import ",
+            "import.",
+            "import.m",
+            "import.meta",
+            "import(",
+            "import/",
+            "import '",
+            "import {",
+            "#!",
+            "/[",
+            "/[/",
+            "a = /[[]/v",
+            "\\",
+            "é",
+            "\u{a0}",
+            "import 'é",
+        ];
+        for source in sources.iter() {
+            check(source);
+        }
+    }
+
+    /// Checks prefixes and suffixes of the fixtures cut at evenly spaced points,
+    /// so that the lexer sees sources ending and starting in the middle of every kind of token.
+    #[test]
+    fn fixture_corpus() {
+        const CUTS: usize = 16;
+
+        for entry in read_dir("fixtures").unwrap() {
+            let source = read_to_string(entry.unwrap().path()).unwrap();
+            for cut in 1..CUTS {
+                let mut idx = source.len() * cut / CUTS;
+                while !source.is_char_boundary(idx) {
+                    idx += 1;
+                }
+                check(&source[..idx]);
+                check(&source[idx..]);
+            }
+        }
+    }
+}
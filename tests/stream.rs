@@ -0,0 +1,96 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{read, read_dir, File};
+    use std::io::ErrorKind;
+
+    use es_module_lexer::{
+        parse_bytes, parse_reader, ParseError, ParseErrorKind, ParseLimits, StreamLexer,
+    };
+
+    use crate::common::{record, Recorder};
+
+    fn stream(source: &[u8], chunk_len: usize, recorder: &mut Recorder) -> Result<(), ParseError> {
+        let mut lexer = StreamLexer::new();
+        for chunk in source.chunks(chunk_len) {
+            lexer.push(chunk, recorder)?;
+        }
+        lexer.finish(recorder)
+    }
+
+    #[test]
+    fn fixtures() {
+        for entry in read_dir("fixtures").unwrap() {
+            let path = entry.unwrap().path();
+            let source = read(&path).unwrap();
+            let (expected, result) = record(std::str::from_utf8(&source).unwrap(), None);
+            result.unwrap();
+            assert!(!expected.is_empty());
+
+            for &chunk_len in &[1, 4093, 1 << 20] {
+                let mut recorder = Recorder::default();
+                stream(&source, chunk_len, &mut recorder).unwrap();
+                assert_eq!(recorder.calls, expected, "{}", path.display());
+            }
+
+            let mut recorder = Recorder::default();
+            parse_reader(File::open(&path).unwrap(), &mut recorder).unwrap();
+            assert_eq!(recorder.calls, expected, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn absolute_error_position() {
+        let mut source = "import './a.js';\r\nf(/a/ / 2);\u{2028}".repeat(20_000);
+        source.push_str("g('h);");
+        let expected = parse_bytes(source.as_bytes()).unwrap_err();
+        assert_eq!(expected.kind, ParseErrorKind::UnterminatedString);
+
+        for &chunk_len in &[1, 1000, 1 << 20] {
+            let err = stream(source.as_bytes(), chunk_len, &mut Recorder::default()).unwrap_err();
+            assert_eq!(
+                (err.idx, err.line, err.col, err.range),
+                (
+                    expected.idx,
+                    expected.line,
+                    expected.col,
+                    expected.range.clone()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn unclosed_bracket_before_window() {
+        let source = format!("f({}", "import('./a.js');\n".repeat(20_000));
+        let err = stream(source.as_bytes(), 1000, &mut Recorder::default()).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnbalancedBracket);
+        assert_eq!((err.idx, err.range), (1, 1..2));
+        // the line of the bracket is no longer known
+        assert_eq!((err.line, err.col), (0, 0));
+    }
+
+    #[test]
+    fn stop_early() {
+        let source = format!("{}{{", "import './a.js';\n".repeat(10_000));
+        let mut recorder = Recorder::stopping_after(5_000);
+        parse_reader(source.as_bytes(), &mut recorder).unwrap();
+        assert_eq!(recorder.calls, record(&source, Some(5_000)).0);
+
+        let err = parse_reader(source.as_bytes(), &mut Recorder::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn source_limit() {
+        let mut lexer = StreamLexer::with_limits(ParseLimits {
+            max_source_bytes: 10,
+            ..ParseLimits::default()
+        });
+        let mut recorder = Recorder::default();
+        lexer.push(b"import", &mut recorder).unwrap();
+        let err = lexer.push(b" './a.js';", &mut recorder).unwrap_err();
+        assert_eq!(err.range, 10..16);
+    }
+}
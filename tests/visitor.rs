@@ -0,0 +1,77 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use es_module_lexer::{parse, parse_with_visitor, Import, Lexer};
+
+    use crate::common::Recorder;
+
+    #[test]
+    fn callbacks() {
+        let source = "import a from './a.js';\nimport(import('./b.js'));\nexport { a };";
+        let mut recorder = Recorder::default();
+        parse_with_visitor(source, &mut recorder).unwrap();
+        assert_eq!(
+            recorder.calls,
+            [
+                "StaticImport { statement_start: 0, start: 15, end: 21, statement_end: 22 }",
+                "DynamicImport { statement_start: 31, start: 38, end: 46 }",
+                "DynamicImport { statement_start: 24, start: 31, end: 47 }",
+                "Export { start: 59, end: 60, local: Some(59..60) }",
+            ]
+        );
+    }
+
+    const IMPORT_AT_START: &str =
+        "StaticImport { statement_start: 0, start: 8, end: 14, statement_end: 15 }";
+
+    #[test]
+    fn stop_early() {
+        // the unclosed brace after the first import is never reached
+        let source = "import './a.js';\nimport './b.js';\n{";
+        let mut recorder = Recorder::stopping_after(1);
+        parse_with_visitor(source, &mut recorder).unwrap();
+        assert_eq!(recorder.calls, [IMPORT_AT_START]);
+
+        let mut recorder = Recorder::default();
+        assert!(parse_with_visitor(source, &mut recorder).is_err());
+        assert_eq!(
+            recorder.calls,
+            [
+                IMPORT_AT_START,
+                "StaticImport { statement_start: 17, start: 25, end: 31, statement_end: 32 }"
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_imports_in_source_order() {
+        let source = "import(import.meta.url + import('./a.js'));";
+        let analysis = parse(source).unwrap();
+        let starts: Vec<usize> = analysis
+            .imports
+            .iter()
+            .map(|import| match import {
+                Import::Static(import) => import.statement_start,
+                Import::Dynamic(import) => import.statement_start,
+                Import::Meta(import) => import.start,
+            })
+            .collect();
+        assert_eq!(starts, [0, 7, 25]);
+    }
+
+    #[test]
+    fn lexer_reuse() {
+        let mut lexer = Lexer::new();
+        let mut recorder = Recorder::default();
+        assert!(lexer
+            .parse_with_visitor("import('./a.js'", &mut recorder)
+            .is_err());
+        assert!(recorder.calls.is_empty());
+
+        lexer
+            .parse_with_visitor("import './b.js';", &mut recorder)
+            .unwrap();
+        assert_eq!(recorder.calls, [IMPORT_AT_START]);
+    }
+}
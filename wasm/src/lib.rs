@@ -0,0 +1,7 @@
+//! The WebAssembly build of the lexer, see the `build-*` targets of the Makefile.
+//!
+//! The bindings are defined by the `es_module_lexer` crate behind its `wasm` feature.
+//! This crate only links them into a `cdylib`, which keeps the lexer itself a plain library
+//! that can be used without `std`.
+
+pub use es_module_lexer::*;
@@ -6,6 +6,9 @@ import fs from 'fs';
 
 const n = 25;
 
+// `node benchmark.mjs flat` measures the flat buffer API instead of the wasm-bindgen classes
+const api = process.argv[2] || 'classes';
+
 const files = fs.readdirSync('fixtures')
 	.map(f => `fixtures/${f}`)
 	.filter(x => x.endsWith('.js'))
@@ -29,7 +32,9 @@ Promise.resolve().then(async () => {
 	console.log('Module load time');
 	{
 		const start = process.hrtime.bigint();
-		var { parse } = await import('../wasm_node/es_module_lexer.js');
+		var { parse } = api === 'flat'
+			? { parse: (await import('../wasm_node/flat.js')).parseFlat }
+			: await import('../wasm_node/es_module_lexer.js');
 		// await init();
 		console.log(`> ${Math.round(Number(process.hrtime.bigint() - start) / 1e6) + 'ms'}`);
 	}
@@ -1,5 +1,6 @@
 import assert from 'https://jspm.dev/webassert@3.0'
-import init, { parse, StaticImport, DynamicImport, ImportMeta, Export } from "../wasm_web/es_module_lexer.js"
+import init, { parse, parseCompat, parseMany, isParseError, StaticImport, DynamicImport, ImportMeta, Export } from "../wasm_web/es_module_lexer.js"
+import { parseFlat } from "../wasm_web/flat.js"
 
 describe("es-modules-lexer", () => {
     describe("init()", () => {
@@ -11,6 +12,17 @@ describe("es-modules-lexer", () => {
         })
     })
 
+    describe("initSync()", () => {
+        it("initializes the inlined wasm module synchronously", async () => {
+            const { initSync, parse } = await import("../wasm_sync/es_module_lexer_sync.js")
+            const start = performance.now()
+            initSync()
+            console.log('initSync took > ', performance.now() - start, 'ms')
+            initSync()
+            assert(parse("import 'a';").imports.length === 1)
+        })
+    })
+
     describe("parse()", () => {
         it("parses imports", () => {
             const source = `
@@ -21,6 +33,9 @@ describe("es-modules-lexer", () => {
             const { imports } = parse(source)
 
             assert(imports[0] instanceof StaticImport)
+            assert(imports[0].kind === "static")
+            assert(imports[1].kind === "dynamic")
+            assert(imports[2].kind === "meta")
             {
                 const {start, end} = imports[0].statementRange()
                 assert(source.substring(start, end) === 'import { foo } from "bar"')
@@ -47,6 +62,70 @@ describe("es-modules-lexer", () => {
             }
         })
 
+        it("applies the limits of the options", () => {
+            const source = "import 'a';\nimport 'b';"
+            assert(parse(source, {}).imports.length === 2)
+            assert(parse(source, { maxImports: 2 }).imports.length === 2)
+            assert.throws(() => parse(source, { maxImports: 1 }))
+            assert.throws(() => parse("((()))", { maxNestingDepth: 2 }))
+        })
+
+        it("throws structured errors", () => {
+            try {
+                parse("const a = 1;\n// 😀\nconst s = '😀 abc")
+                assert(false)
+            } catch (err) {
+                assert(err instanceof Error)
+                assert(isParseError(err))
+                assert(err.name === "ParseError")
+                assert(err.kind === "UnterminatedString")
+                assert(err.limit === undefined)
+                assert(err.idx === 29)
+                assert(err.line === 3)
+                assert(err.column === 11)
+                assert(err.message.includes("unterminated string"))
+                assert(err.message.includes("const s = '😀 abc"))
+            }
+            try {
+                parse("((()))", { maxNestingDepth: 2 })
+                assert(false)
+            } catch (err) {
+                assert(err.kind === "LimitExceeded")
+                assert(err.limit === "NestingDepth")
+            }
+            assert(!isParseError(new Error("parse error")))
+        })
+
+        it("returns UTF-16 offsets after non-ASCII text", () => {
+            const source = `
+                // 🎉 절대 경로 — 日本語のコメント
+                const greeting = "👋🏽 你好";
+                import { foo } from "bar";
+                import("./dynamicModule.js")
+                export { greeting }
+            `
+            const { imports, exports } = parse(source)
+            {
+                const { start, end } = imports[0].statementRange()
+                assert(source.substring(start, end) === 'import { foo } from "bar"')
+            }
+            {
+                const { start, end, byteStart, byteEnd } = imports[0].moduleSpecifierRange()
+                assert(source.substring(start, end) === "bar")
+                const bytes = new TextEncoder().encode(source)
+                assert(new TextDecoder().decode(bytes.subarray(byteStart, byteEnd)) === "bar")
+                assert(byteStart > start)
+            }
+            {
+                const { start, end } = imports[1].moduleSpecifierExpressionRange()
+                assert(source.substring(start, end) === '"./dynamicModule.js"')
+            }
+            {
+                const { start, end } = exports[0].exportSpecifierRange()
+                assert(source.substring(start, end) === "greeting")
+            }
+        })
+
         it("parses exports", () => {
             const source = `
                 const much = "";
@@ -76,4 +155,142 @@ describe("es-modules-lexer", () => {
             }
         })
     })
+
+    describe("parseCompat()", () => {
+        it("returns imports and exports in the es-module-lexer shape", () => {
+            const source = `
+                // 日本語 😀
+                import { foo } from "./\\x62ar.js";
+                const m = import('./dynamic.js');
+                import(specifier);
+                console.log(import.meta.url);
+                export { foo as bar };
+            `
+            const [imports, exports, facade, hasModuleSyntax] = parseCompat(source)
+
+            assert(imports.length === 4)
+            {
+                const { n, s, e, ss, se, d, a } = imports[0]
+                assert(n === "./bar.js")
+                assert(source.slice(s, e) === "./\\x62ar.js")
+                assert(source.slice(ss, se) === 'import { foo } from "./\\x62ar.js"')
+                assert(d === -1 && a === -1)
+            }
+            {
+                const { n, s, e, ss, se, d } = imports[1]
+                assert(n === "./dynamic.js")
+                assert(source.slice(s, e) === "'./dynamic.js'")
+                assert(source.slice(ss, se) === "import('./dynamic.js')")
+                assert(source[d] === "(")
+            }
+            assert(imports[2].n === undefined && imports[2].d > -1)
+            {
+                const { n, ss, se, d } = imports[3]
+                assert(n === undefined && d === -2)
+                assert(source.slice(ss, se) === "import.meta")
+            }
+
+            assert(exports.length === 1)
+            assert(exports[0].n === "bar")
+            assert(source.slice(exports[0].s, exports[0].e) === "bar")
+            assert(facade === false)
+            assert(hasModuleSyntax === true)
+        })
+
+        it("detects facades", () => {
+            const [, , facade, hasModuleSyntax] = parseCompat("export * from './a.js';\nexport { b } from './b.js';")
+            assert(facade === true)
+            assert(hasModuleSyntax === true)
+        })
+
+        it("throws errors with an index", () => {
+            try {
+                parseCompat("// 😀\nimport 'a", "file.js")
+                assert(false)
+            } catch (err) {
+                assert(err.message === "Parse error file.js:2:8")
+                assert(err.idx === 13)
+            }
+        })
+
+        it("counts columns in UTF-16 code units", () => {
+            try {
+                parseCompat("/* 😀 */ import 'a")
+                assert(false)
+            } catch (err) {
+                assert(err.message === "Parse error @:1:16")
+                assert(err.idx === 15)
+            }
+        })
+
+        it("applies the limits", () => {
+            assert.throws(() => parseCompat("import 'a';\nimport 'b';", "a.js", { maxImports: 1 }), /Parse error a\.js:2:/)
+            assert(parseCompat("import 'a';", "a.js", { maxImports: 1 })[0].length === 1)
+        })
+    })
+
+    describe("parseFlat()", () => {
+        it("matches parseCompat", () => {
+            const source = `
+                // 日本語 😀
+                import { foo } from "./foo.js";
+                const m = import('./dynamic.js');
+                console.log(import.meta.url);
+                export { foo as bar };
+            `
+            assert.deepEqual(parseFlat(source), parseCompat(source))
+        })
+
+        it("parses sources larger than the previous one", () => {
+            parseFlat("import 'a';")
+            const source = "import 'a';\n".repeat(1000) + "export const b = '😀';"
+            const [imports, exports] = parseFlat(source)
+            assert(imports.length === 1000)
+            assert(source.slice(exports[0].s, exports[0].e) === "b")
+        })
+
+        it("throws errors with an index", () => {
+            try {
+                parseFlat("// 😀\nimport 'a", "file.js")
+                assert(false)
+            } catch (err) {
+                assert(err.message === "Parse error file.js:2:8")
+                assert(err.idx === 13)
+            }
+        })
+
+        it("applies the limits", () => {
+            assert.throws(() => parseFlat("import 'a';\nimport 'b';", "a.js", { maxImports: 1 }), /Parse error a\.js:2:/)
+        })
+    })
+
+    describe("parseMany()", () => {
+        it("parses every source", () => {
+            const sources = [
+                "import 'a';",
+                "// 😀\nexport const b = 1;",
+                "",
+            ]
+            const results = parseMany(sources)
+            assert(results.length === 3)
+            results.forEach((result, i) => {
+                assert.deepEqual(result, parseCompat(sources[i]))
+            })
+        })
+
+        it("attaches errors to their source", () => {
+            const results = parseMany(["import 'a';", "// 😀\nimport 'a", "export { c }"], ["a.js", "b.js", "c.js"])
+            assert(results[0][0][0].n === "a")
+            assert(results[1] instanceof Error)
+            assert(results[1].message === "Parse error b.js:2:8")
+            assert(results[1].idx === 13)
+            assert(results[2][1][0].n === "c")
+        })
+
+        it("rejects values that are not strings", () => {
+            const results = parseMany([42, "import 'a';"])
+            assert(results[0] instanceof TypeError)
+            assert(results[1][0].length === 1)
+        })
+    })
 })
@@ -0,0 +1,24 @@
+/*
+ * Tests the CommonJS build for Node with `require`, run with `make wasm-node-test`.
+ */
+const assert = require('assert');
+const { parse, parseCompat } = require('../wasm_node/es_module_lexer.js');
+const { parseFlat } = require('../wasm_node/flat.js');
+
+const source = `
+	// 😀
+	import { foo } from "./foo.js";
+	import('./dynamic.js');
+	export { foo as bar };
+`;
+
+const { imports, exports } = parse(source);
+assert.strictEqual(imports.length, 2);
+{
+	const { start, end } = exports[0].exportSpecifierRange();
+	assert.strictEqual(source.slice(start, end), 'bar');
+}
+
+assert.deepStrictEqual(parseFlat(source), parseCompat(source));
+
+console.log('ok');
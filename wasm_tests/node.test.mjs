@@ -0,0 +1,36 @@
+/*
+ * Tests the synchronously initialized build in Node, run with `make wasm-node-test`.
+ */
+import assert from 'assert';
+import { initSync, parse, parseCompat } from '../wasm_sync/es_module_lexer_sync.js';
+import { parseFlat } from '../wasm_sync/flat.js';
+
+assert.throws(() => parse("import 'a';"));
+
+initSync();
+initSync();
+
+const source = `
+	// 😀
+	import { foo } from "./foo.js";
+	import('./dynamic.js');
+	export { foo };
+	export { foo as bar };
+	export default function baz () {}
+`;
+
+const { imports, exports } = parse(source);
+assert.strictEqual(imports.length, 2);
+assert.strictEqual(imports[0].kind, 'static');
+{
+	const { start, end } = imports[0].moduleSpecifierRange();
+	assert.strictEqual(source.slice(start, end), './foo.js');
+}
+{
+	const { start, end } = exports[0].exportSpecifierRange();
+	assert.strictEqual(source.slice(start, end), 'foo');
+}
+
+assert.deepStrictEqual(parseFlat(source), parseCompat(source));
+
+console.log('ok');
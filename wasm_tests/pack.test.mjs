@@ -0,0 +1,29 @@
+/*
+ * Imports the packed sync build by its package name, run with `make wasm-pack-test`
+ * from the clean directory it was installed into.
+ */
+import assert from 'assert';
+import { createRequire } from 'module';
+import path from 'path';
+import { pathToFileURL } from 'url';
+
+const name = process.argv[2];
+// resolved from the working directory, not from this file
+const require = createRequire(path.join(process.cwd(), 'index.js'));
+const load = specifier => import(pathToFileURL(require.resolve(specifier)).href);
+
+const { initSync, parse, parseCompat } = await load(name);
+const { parseFlat } = await load(`${name}/flat.js`);
+
+initSync();
+
+const source = `
+	import { foo } from "./foo.js";
+	export { foo as bar };
+`;
+assert.strictEqual(parse(source).imports.length, 1);
+assert.deepStrictEqual(parseFlat(source), parseCompat(source));
+// the error class is an inline JS snippet of the glue
+assert.throws(() => parse("'"), { name: 'ParseError' });
+
+console.log('ok');
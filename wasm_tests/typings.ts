@@ -0,0 +1,68 @@
+/*
+ * Type checks the generated typings, run with `make check-typings`.
+ */
+import {
+    parse, parseCompat, parseMany, isParseError, Import, Limit, ParseError, ParseErrorKind, ParseOptions, Range,
+} from "../wasm_web/es_module_lexer.js"
+import { parseFlat } from "../wasm_web/flat.js"
+
+const source = "import a from './a.js'; export { a };"
+
+const options: ParseOptions = { maxNestingDepth: 64, maxImports: 100 }
+const { imports, exports } = parse(source, options)
+
+function specifierRange(i: Import): Range {
+    switch (i.kind) {
+        case "static":
+            return i.moduleSpecifierRange()
+        case "dynamic":
+            return i.moduleSpecifierExpressionRange()
+        case "meta":
+            return i.expressionRange()
+    }
+}
+
+const ranges: Range[] = imports.map(specifierRange)
+const names: string[] = exports.map(e => {
+    const { start, end, byteStart, byteEnd }: { start: number, end: number, byteStart: number, byteEnd: number } =
+        e.exportSpecifierRange()
+    return source.slice(start, end) + byteStart + byteEnd
+})
+
+try {
+    parse("'")
+} catch (err) {
+    if (isParseError(err)) {
+        const parseError = err as ParseError
+        const { idx, line, column, message }: ParseError = parseError
+        const kind: ParseErrorKind["kind"] = parseError.kind
+        if (parseError.kind === "LimitExceeded") {
+            const limit: Limit = parseError.limit
+        }
+        // @ts-expect-error only the `LimitExceeded` kind has a limit
+        parseError.limit
+    }
+}
+
+const [compatImports, compatExports, facade, hasModuleSyntax] = parseCompat(source, "a.js")
+parseCompat(source, undefined, options)
+parseMany([source], undefined, options)
+const specifiers: (string | undefined)[] = compatImports.map(i => i.n)
+const dynamic: boolean = compatImports.some(i => i.t === 2 && i.d > -1)
+const exported: string[] = compatExports.map(e => e.n)
+const flags: boolean[] = [facade, hasModuleSyntax]
+
+// @ts-expect-error the sources must be strings
+parseMany([1])
+
+for (const result of parseMany([source], ["a.js", undefined])) {
+    if (result instanceof Error) {
+        const idx: number = result.idx
+    } else {
+        const [imports]: [typeof compatImports, ...unknown[]] = result
+    }
+}
+
+const [flatImports] = parseFlat(source, "a.js")
+parseFlat(source, "a.js", options)
+const flatSpecifiers: (string | undefined)[] = flatImports.map(i => i.n)
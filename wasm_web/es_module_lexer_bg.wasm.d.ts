@@ -1,24 +0,0 @@
-/* tslint:disable */
-/* eslint-disable */
-export const memory: WebAssembly.Memory;
-export function parse(a: number, b: number): number;
-export function __wbg_sourceanalysis_free(a: number): void;
-export function sourceanalysis_imports(a: number): number;
-export function sourceanalysis_exports(a: number): number;
-export function __wbg_staticimport_free(a: number): void;
-export function staticimport_statementRange(a: number): number;
-export function __wbg_dynamicimport_free(a: number): void;
-export function dynamicimport_moduleSpecifierExpressionRange(a: number): number;
-export function dynamicimport_importExpressionRange(a: number): number;
-export function __wbg_export_free(a: number): void;
-export function export_exportSpecifierRange(a: number): number;
-export function __wbg_get_range_start(a: number): number;
-export function __wbg_set_range_start(a: number, b: number): void;
-export function __wbg_get_range_end(a: number): number;
-export function __wbg_set_range_end(a: number, b: number): void;
-export function __wbg_importmeta_free(a: number): void;
-export function __wbg_range_free(a: number): void;
-export function staticimport_moduleSpecifierRange(a: number): number;
-export function importmeta_expressionRange(a: number): number;
-export function __wbindgen_malloc(a: number): number;
-export function __wbindgen_realloc(a: number, b: number, c: number): number;